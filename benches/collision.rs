@@ -0,0 +1,89 @@
+//! Collision broadphase benchmark, run with `cargo bench --bench collision`.
+//!
+//! Fails if a single tick worth of collision checks doesn't fit in the 33 ms
+//! tick budget of the event loop.
+
+use normal_game_jam_2024::app::Bullet;
+use normal_game_jam_2024::collision::{self, Collider, SpatialHash, Target};
+use std::time::{Duration, Instant};
+
+const TICK_BUDGET: Duration = Duration::from_millis(33);
+const ROUNDS: u32 = 100;
+
+/// Spread bullets over the arena with a golden-angle spiral
+fn bullets(count: usize) -> Vec<Bullet> {
+    const GOLDEN_ANGLE: f64 = 2.399_963_229_728_653;
+    (0..count)
+        .map(|i| {
+            let r = 100. * (i as f64 / count as f64).sqrt();
+            let theta = i as f64 * GOLDEN_ANGLE;
+            Bullet {
+                pos_x: r * theta.cos(),
+                pos_y: r * theta.sin() * 0.5,
+                velocity_x: theta.sin(),
+                velocity_y: theta.cos(),
+                is_player: i % 4 == 0,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn targets() -> Vec<(Target, Collider)> {
+    let player = (
+        Target::Player,
+        Collider::Circle {
+            x: 0.,
+            y: 0.,
+            radius: 2.,
+        },
+    );
+    let enemies = (0..8).map(|i| {
+        let theta = i as f64 * std::f64::consts::PI / 4.;
+        (
+            Target::Enemy,
+            Collider::Circle {
+                x: 40. * theta.cos(),
+                y: 20. * theta.sin(),
+                radius: 3.,
+            },
+        )
+    });
+    std::iter::once(player).chain(enemies).collect()
+}
+
+fn main() {
+    let targets = targets();
+    let mut grid = SpatialHash::default();
+    let mut failed = false;
+
+    for count in [1_000, 5_000, 10_000, 20_000] {
+        let mut bullets = bullets(count);
+        let mut hits = 0;
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            // move a little so each round works on a different layout
+            for b in &mut bullets {
+                b.prev_x = b.pos_x;
+                b.prev_y = b.pos_y;
+                b.pos_x += b.velocity_x * 0.033;
+                b.pos_y += b.velocity_y * 0.033;
+            }
+            grid.rebuild(&bullets);
+            hits += collision::detect(&grid, &bullets, &targets).len();
+        }
+        let per_tick = start.elapsed() / ROUNDS;
+
+        let ok = per_tick <= TICK_BUDGET;
+        failed |= !ok;
+        println!(
+            "{count:>6} bullets: {per_tick:>10.2?} / tick, {hits} hits [{}]",
+            if ok { "ok" } else { "OVER BUDGET" }
+        );
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
@@ -1,24 +1,37 @@
 use std::{error, fmt::Debug, time::Duration};
 
-use crossterm::event::{MouseEvent, MouseEventKind};
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::Rect,
     style::Color,
     widgets::canvas::{self, Circle, Points, Shape},
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    battle::{create_enemy, Enemy, EnemyAction},
-    skill::Skill,
+    arena::Arena,
+    battle::{create_stage, Affinity, Enemy, EnemyAction},
+    camera::Camera,
+    collision::{self, Collider, HitEvent, SpatialHash, Target},
+    input::HeldKeys,
+    keymap::{KeyConfig, RebindMenu},
+    obstacle::Obstacle,
+    replay::Replay,
+    rng::Rng,
+    skill::{Language, Skill},
+    status::{StatusEffect, StatusEffects},
+    viewport::Viewport,
 };
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Player {
     pub pos_x: f64,
     pub pos_y: f64,
+    /// Position at the start of the last simulation step
+    pub prev_x: f64,
+    pub prev_y: f64,
     pub face_x: f64,
     pub face_y: f64,
     pub hp: isize,
@@ -27,25 +40,195 @@ pub struct Player {
     pub max_mp: isize,
     pub move_velocity: f64,
     pub size: f64,
+    /// Radius bullets hit within while focused, smaller than `size`
+    pub hitbox: f64,
+    /// Moving slowly, with the smaller hitbox shown
+    pub focused: bool,
+    /// Bombs left
+    pub bombs: usize,
+    /// Time left of the last bomb's blast on screen
+    pub bomb_flash: Duration,
     pub skills: Vec<Skill>,
+    pub statuses: StatusEffects,
+    /// How long the next shot has been charged, if it's being charged
+    pub charge: Option<Duration>,
+    pub loadouts: Vec<Loadout>,
+    /// Index into `loadouts` of the one in use
+    pub loadout: usize,
+    /// Direction and time left of the dash in progress
+    pub dash: Option<((f64, f64), Duration)>,
+    /// Time until the player can dash again
+    pub dash_cooldown: Duration,
+    /// Time left bullets pass through the player
+    pub invulnerable: Duration,
+    /// Positions along the last dash, and how long they're still drawn
+    pub trail: Vec<(f64, f64)>,
+    pub trail_remaining: Duration,
 }
 
 /// In-game events
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum GameEvent {
     /// move to direction
     PlayerMove(f64, f64),
     /// shoot aim to direction
     Shoot(f64, f64),
+    /// a spell was recognized
+    Cast(Language),
+    /// start charging the next shot
+    Charge,
+    /// drop the charge without shooting
+    CancelCharge,
+    /// shoot the secondary spell of the loadout aim to position
+    ShootSecondary(f64, f64),
+    /// shoot a spread of the primary spell aim to position
+    Special(f64, f64),
+    /// switch to the next (1) or previous (-1) loadout
+    CycleLoadout(i8),
+    /// dash along the move direction, or facing if standing still
+    Dash,
+    /// move slowly this step
+    Focus,
+    /// clear enemy bullets around the player
+    Bomb,
+}
+
+/// Spells bound to the primary and secondary fire, `None` for plain shots
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Loadout {
+    pub primary: Option<Language>,
+    pub secondary: Option<Language>,
+}
+
+/// Which fire a shot came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Shot {
+    Primary,
+    Secondary,
+    Special,
 }
 
 impl Player {
+    /// Longest a shot can be charged
+    pub const MAX_CHARGE: Duration = Duration::from_millis(1500);
+    /// Charging drains 1 MP this often
+    const CHARGE_MP_INTERVAL: Duration = Duration::from_millis(300);
+    /// Extra damage of a fully charged shot
+    const CHARGE_DAMAGE: f64 = 4.;
+
+    /// Bullets in a special shot
+    const SPECIAL_BULLETS: usize = 5;
+    /// Degrees between bullets of a special shot
+    const SPECIAL_SPREAD: f64 = 10.;
+    const SPECIAL_MP: isize = 3;
+
+    /// Speed of a dash, in units per second
+    const DASH_SPEED: f64 = 40.;
+    const DASH_TIME: Duration = Duration::from_millis(150);
+    pub const DASH_COOLDOWN: Duration = Duration::from_secs(1);
+    /// Invulnerability from dashing, a bit longer than the dash itself
+    const DASH_IFRAMES: Duration = Duration::from_millis(250);
+    /// Invulnerability after being hit, so a cluster of bullets only hits once
+    const HIT_IFRAMES: Duration = Duration::from_millis(500);
+    /// How long the dash trail stays on screen after the dash
+    const TRAIL_TIME: Duration = Duration::from_millis(300);
+
+    /// Speed multiplier while focused
+    const FOCUS_SPEED: f64 = 0.4;
+    /// How close to the hitbox a bullet must pass to graze
+    const GRAZE_DISTANCE: f64 = 2.5;
+    /// MP gained for each bullet grazed
+    const GRAZE_MP: isize = 1;
+
+    /// Enemy bullets this close are cleared by a bomb
+    pub const BOMB_RADIUS: f64 = 40.;
+    /// Bullets cleared by a bomb for each MP gained
+    const BOMB_BULLETS_PER_MP: usize = 2;
+    const BOMB_IFRAMES: Duration = Duration::from_secs(1);
+    const BOMB_FLASH_TIME: Duration = Duration::from_millis(400);
+
+    pub fn loadout(&self) -> Loadout {
+        self.loadouts.get(self.loadout).copied().unwrap_or_default()
+    }
+
+    /// How charged the next shot is, in `[0, 1]`
+    pub fn charge_ratio(&self) -> f64 {
+        self.charge
+            .map_or(0., |c| c.as_secs_f64() / Self::MAX_CHARGE.as_secs_f64())
+    }
+
+    /// Keep charging, returns the MP drained.
+    ///
+    /// The charge stops growing at the cap, or when only the MP for the shot
+    /// itself is left.
+    fn charge(&mut self, delta: Duration) -> isize {
+        let Some(charge) = self.charge else {
+            return 0;
+        };
+        if charge >= Self::MAX_CHARGE || self.mp <= 1 {
+            return 0;
+        }
+
+        let next = (charge + delta).min(Self::MAX_CHARGE);
+        let interval = Self::CHARGE_MP_INTERVAL.as_nanos();
+        let cost = (next.as_nanos() / interval - charge.as_nanos() / interval) as isize;
+        self.charge = Some(next);
+        self.mp -= cost;
+        cost
+    }
+
+    /// Radius bullets and hazards hit within
+    pub fn hit_radius(&self) -> f64 {
+        if self.focused {
+            self.hitbox
+        } else {
+            self.size
+        }
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerable.is_zero()
+    }
+
+    /// Start a dash along (x, y), or facing if that's zero. Returns false if
+    /// still cooling down.
+    fn start_dash(&mut self, x: f64, y: f64) -> bool {
+        if self.dash.is_some() || !self.dash_cooldown.is_zero() {
+            return false;
+        }
+        let (x, y) = crate::norm(x, y);
+        let direction = if (x, y) != (0., 0.) {
+            (x, y)
+        } else {
+            (self.face_x, self.face_y)
+        };
+        self.dash = Some((direction, Self::DASH_TIME));
+        self.dash_cooldown = Self::DASH_COOLDOWN;
+        self.invulnerable = self.invulnerable.max(Self::DASH_IFRAMES);
+        self.trail = vec![(self.pos_x, self.pos_y)];
+        self.trail_remaining = Self::TRAIL_TIME;
+        true
+    }
+
+    /// Count down dash, bomb and invulnerability timers
+    fn tick_timers(&mut self, delta: Duration) {
+        self.dash_cooldown = self.dash_cooldown.saturating_sub(delta);
+        self.invulnerable = self.invulnerable.saturating_sub(delta);
+        self.bomb_flash = self.bomb_flash.saturating_sub(delta);
+        if self.dash.is_none() {
+            self.trail_remaining = self.trail_remaining.saturating_sub(delta);
+            if self.trail_remaining.is_zero() {
+                self.trail.clear();
+            }
+        }
+    }
+
     pub fn walk(&mut self, delta_x: f64, delta_y: f64) -> AppResult<()> {
         self.pos_x += delta_x;
         self.pos_y += delta_y;
 
         let (delta_x, delta_y) = crate::norm(delta_x, delta_y);
-        if (delta_x + delta_y).abs() > 0. {
+        if (delta_x, delta_y) != (0., 0.) {
             self.face_x = delta_x;
             self.face_y = delta_y;
         }
@@ -54,19 +237,26 @@ impl Player {
     }
 
     /// Create a bullet aim to (sx, sy)
-    pub fn new_bullet(&self, sx: f64, sy: f64) -> Bullet {
+    pub fn new_bullet(&self, sx: f64, sy: f64, language: Option<Language>) -> Bullet {
         const BULLET_VELOCITY: f64 = 12.;
         const BULLET_OFFSET: f64 = 1.5;
+        // even, so resisted hits halve it
+        const BULLET_DAMAGE: isize = 2;
 
         let delta_x = sx - self.pos_x;
         let delta_y = sy - self.pos_y;
         let (delta_x, delta_y) = crate::norm(delta_x, delta_y);
+        let charge = (self.charge_ratio() * Self::CHARGE_DAMAGE).round() as isize;
         Bullet {
             pos_x: self.pos_x + delta_x * BULLET_OFFSET,
             pos_y: self.pos_y + delta_y * BULLET_OFFSET,
             velocity_x: delta_x * BULLET_VELOCITY,
             velocity_y: delta_y * BULLET_VELOCITY,
             is_player: true,
+            damage: BULLET_DAMAGE + charge,
+            language,
+            effect: language.and_then(|l| l.effect()),
+            ricochet: language.is_some_and(|l| l.ricochet()),
             ..Default::default()
         }
     }
@@ -74,13 +264,55 @@ impl Player {
 
 impl Shape for Player {
     fn draw(&self, painter: &mut canvas::Painter) {
+        Interpolated(self, 1.).draw(painter);
+    }
+}
+
+impl Shape for Interpolated<'_, Player> {
+    fn draw(&self, painter: &mut canvas::Painter) {
+        let Interpolated(player, alpha) = *self;
+        let x = crate::lerp(player.prev_x, player.pos_x, alpha);
+        let y = crate::lerp(player.prev_y, player.pos_y, alpha);
+        if !player.bomb_flash.is_zero() {
+            // the blast grows out to the bomb radius
+            let progress =
+                1. - player.bomb_flash.as_secs_f64() / Player::BOMB_FLASH_TIME.as_secs_f64();
+            Circle {
+                x,
+                y,
+                radius: Player::BOMB_RADIUS * progress,
+                color: Color::Magenta,
+            }
+            .draw(painter);
+        }
+        if !player.trail.is_empty() {
+            Points {
+                coords: &player.trail,
+                color: Color::Cyan,
+            }
+            .draw(painter);
+        }
+        // fade out while invulnerable
+        let color = if player.is_invulnerable() {
+            Color::DarkGray
+        } else {
+            player.statuses.tint().unwrap_or(Color::White)
+        };
         let circle = Circle {
-            x: self.pos_x,
-            y: self.pos_y,
-            radius: self.size,
-            color: Color::White,
+            x,
+            y,
+            radius: player.size,
+            color,
         };
         circle.draw(painter);
+        if player.focused {
+            Circle {
+                radius: player.hitbox,
+                color: Color::LightRed,
+                ..circle
+            }
+            .draw(painter);
+        }
     }
 }
 
@@ -88,10 +320,23 @@ impl Shape for Player {
 pub struct Bullet {
     pub pos_x: f64,
     pub pos_y: f64,
+    /// Position before the last move, used for continuous collision and
+    /// render interpolation
+    pub prev_x: f64,
+    pub prev_y: f64,
     pub velocity_x: f64,
     pub velocity_y: f64,
     pub is_player: bool,
     pub will_remove: bool,
+    pub damage: isize,
+    /// Language of the spell this bullet was casted with
+    pub language: Option<Language>,
+    /// Status effect applied on the target when hit
+    pub effect: Option<StatusEffect>,
+    /// Bounce off the arena walls instead of flying away
+    pub ricochet: bool,
+    /// Within graze distance of the player, counted once it gets away
+    pub grazed: bool,
 }
 
 impl Bullet {
@@ -105,9 +350,19 @@ impl Bullet {
 
 impl Shape for Bullet {
     fn draw(&self, painter: &mut canvas::Painter) {
+        Interpolated(self, 1.).draw(painter);
+    }
+}
+
+impl Shape for Interpolated<'_, Bullet> {
+    fn draw(&self, painter: &mut canvas::Painter) {
+        let Interpolated(bullet, alpha) = *self;
         let points = Points {
-            coords: &[(self.pos_x, self.pos_y)],
-            color: if self.is_player {
+            coords: &[(
+                crate::lerp(bullet.prev_x, bullet.pos_x, alpha),
+                crate::lerp(bullet.prev_y, bullet.pos_y, alpha),
+            )],
+            color: if bullet.is_player {
                 Color::Yellow
             } else {
                 Color::Red
@@ -117,11 +372,67 @@ impl Shape for Bullet {
     }
 }
 
-#[derive(Debug)]
+/// Where keyboard shots go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AimMode {
+    /// Along the direction the player last walked in
+    #[default]
+    Facing,
+    /// At the nearest enemy
+    Auto,
+    /// At a cursor moved with the aim keys
+    Cursor,
+}
+
+impl AimMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AimMode::Facing => "facing",
+            AimMode::Auto => "auto",
+            AimMode::Cursor => "cursor",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            AimMode::Facing => AimMode::Auto,
+            AimMode::Auto => AimMode::Cursor,
+            AimMode::Cursor => AimMode::Facing,
+        }
+    }
+}
+
+/// Draw a shape between its previous and current simulation step,
+/// `alpha` in `[0, 1]` is how far rendering is into the next step.
+pub struct Interpolated<'a, T>(pub &'a T, pub f64);
+
+#[derive(Debug, Clone)]
 pub struct GameLog(pub String);
 
+/// How a run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameResult {
+    Cleared,
+    Defeated,
+}
+
+/// Numbers collected over a run, for balance checks
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct GameStats {
+    /// HP lost, before healing of any kind
+    pub damage_taken: isize,
+    pub mp_used: isize,
+    pub shots: usize,
+    /// Bullets grazed on each stage
+    pub grazes: Vec<usize>,
+    pub bombs_used: usize,
+    /// Step on which each stage was cleared
+    pub stage_clears: Vec<u64>,
+}
+
 /// Application.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct App {
     /// Is the application running?
     pub running: bool,
@@ -130,15 +441,49 @@ pub struct App {
     pub logs: Vec<GameLog>,
     pub bullets: Vec<Bullet>,
     pub events: Vec<GameEvent>,
-    pub world_width: f64,
+    /// Keys held down by the player
+    pub keys: HeldKeys,
+    /// Direction the player is holding, applied on every step
+    pub movement: (f64, f64),
+    /// Whether the player is holding focus, applied on every step
+    pub focus: bool,
+    pub bindings: KeyConfig,
+    /// The key bindings screen, if it's open
+    pub rebind: Option<RebindMenu>,
+    pub paused: bool,
+    pub camera: Camera,
+    pub arena: Arena,
     pub enemy: Box<dyn Enemy>,
+    pub obstacles: Vec<Obstacle>,
     pub casting: bool,
-    // hack for calculate player shoot direction
-    pub canvas_rect: Rect,
+    pub aim: AimMode,
+    /// World position under the mouse
+    pub hover: Option<(f64, f64)>,
+    /// Free-aim cursor, in world coordinates
+    pub cursor: (f64, f64),
+    /// Where the game was last drawn, to map the mouse back into the world
+    pub viewport: Viewport,
+    /// Simulation steps run so far
+    pub steps: u64,
+    /// Seed of `rng`, shown on the results screen to reproduce the run
+    pub seed: u64,
+    pub rng: Rng,
+    /// Set once the run is over
+    pub result: Option<GameResult>,
+    pub stats: GameStats,
+    /// Inputs recorded so far, if recording
+    pub recording: Option<Replay>,
+    /// Replay fed into the simulation instead of live input
+    pub playback: Option<Replay>,
+    /// Elapsed time not simulated yet
+    accumulator: Duration,
+    /// Broadphase grid, rebuilt every tick
+    grid: SpatialHash,
 }
 
 impl Default for App {
     fn default() -> Self {
+        let stage = create_stage(0).unwrap();
         Self {
             running: true,
             player: Player {
@@ -149,34 +494,173 @@ impl Default for App {
                 move_velocity: 6.,
                 face_x: 1.,
                 size: 2.,
+                hitbox: 0.75,
+                bombs: 3,
+                loadouts: vec![
+                    Loadout {
+                        primary: None,
+                        secondary: Some(Language::C),
+                    },
+                    Loadout {
+                        primary: Some(Language::Python),
+                        secondary: Some(Language::Cpp),
+                    },
+                    Loadout {
+                        primary: Some(Language::Go),
+                        secondary: None,
+                    },
+                ],
                 ..Player::default()
             },
             stage_index: 0,
-            enemy: create_enemy(0).unwrap(),
-            world_width: 100.,
+            enemy: stage.enemy,
+            obstacles: stage.obstacles,
+            arena: stage.arena,
+            camera: Camera::default(),
             logs: vec![],
             bullets: vec![],
             events: vec![],
-            canvas_rect: Rect::default(),
+            keys: HeldKeys::default(),
+            movement: (0., 0.),
+            focus: false,
+            bindings: KeyConfig::default(),
+            rebind: None,
+            paused: false,
+            viewport: Viewport::default(),
             casting: false,
+            aim: AimMode::default(),
+            hover: None,
+            cursor: (0., 0.),
+            steps: 0,
+            seed: 0,
+            rng: Rng::new(0),
+            result: None,
+            stats: GameStats::default(),
+            recording: None,
+            playback: None,
+            accumulator: Duration::ZERO,
+            grid: SpatialHash::default(),
         }
     }
 }
 
 impl App {
     pub const CHAR_RATIO: f64 = 2.;
+    /// Length of one simulation step, the simulation always advances by
+    /// exactly this much so the same inputs give the same game state.
+    pub const FIXED_STEP: Duration = Duration::from_nanos(1_000_000_000 / 30);
+    /// Moves of bullets per step, so fast ones don't skip through thin walls
+    /// and obstacles, which only block bullets they end up inside
+    const BULLET_SUBSTEPS: u32 = 2;
+    /// Damage to the enemy that shakes the camera
+    const BIG_HIT: isize = 4;
+    /// Longest elapsed time simulated at once, drop the rest if we lag behind
+    const MAX_FRAME: Duration = Duration::from_millis(250);
+    /// Saying this sets off a bomb
+    pub const BOMB_WORD: &'static str = "bomb";
 
     /// Constructs a new instance of [`App`].
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Constructs a new instance of [`App`] whose randomness comes from `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+            ..Self::default()
+        }
+    }
+
+    /// Replace the enemy, obstacles and arena with those of stage `index` and
+    /// snap the camera to the player, returns false if there's no such stage.
+    pub fn load_stage(&mut self, index: usize) -> bool {
+        let Some(stage) = create_stage(index) else {
+            return false;
+        };
+        self.stage_index = index;
+        self.enemy = stage.enemy;
+        self.obstacles = stage.obstacles;
+        self.arena = stage.arena;
+        self.camera.reset(self.player.pos_x, self.player.pos_y);
+        true
+    }
+
+    /// Constructs a new instance of [`App`] playing back `replay`.
+    pub fn from_replay(replay: Replay) -> Self {
+        let mut app = Self::with_seed(replay.seed);
+        if !replay.is_compatible() {
+            app.logs.push(GameLog(format!(
+                "replay recorded with version {}, it may not play back correctly",
+                replay.version
+            )));
+        }
+        app.playback = Some(replay);
+        app
+    }
+
     /// Handles the tick event of the terminal.
-    pub fn tick(&mut self, delta: Duration) {
+    ///
+    /// Runs as many fixed steps as fit in the elapsed wall-clock time.
+    pub fn update(&mut self, elapsed: Duration) {
+        if self.paused || self.rebind.is_some() {
+            return;
+        }
+        self.accumulator += elapsed.min(Self::MAX_FRAME);
+        while self.accumulator >= Self::FIXED_STEP {
+            self.accumulator -= Self::FIXED_STEP;
+            self.step();
+        }
+    }
+
+    /// Advance the simulation by one fixed step.
+    pub fn step(&mut self) {
+        if self.result.is_some() {
+            return;
+        }
+
+        if let Some(replay) = &mut self.playback {
+            self.events = replay.events_at(self.steps);
+        } else {
+            if self.movement != (0., 0.) {
+                let (x, y) = self.movement;
+                self.events.push(GameEvent::PlayerMove(x, y));
+            }
+            if self.focus {
+                self.events.push(GameEvent::Focus);
+            }
+        }
+        if let Some(recording) = &mut self.recording {
+            for evt in &self.events {
+                recording.record(self.steps, *evt);
+            }
+            recording.length = self.steps + 1;
+        }
+
+        self.tick(Self::FIXED_STEP);
+        self.camera
+            .follow(self.player.pos_x, self.player.pos_y, Self::FIXED_STEP);
+        self.steps += 1;
+    }
+
+    /// How far rendering is between the last and the next step, in `[0, 1]`
+    pub fn alpha(&self) -> f64 {
+        self.accumulator.as_secs_f64() / Self::FIXED_STEP.as_secs_f64()
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        let hp = self.player.hp;
+
         // player
+        self.player.prev_x = self.player.pos_x;
+        self.player.prev_y = self.player.pos_y;
         let mut player_move_x = 0.;
         let mut player_move_y = 0.;
         let mut shoot = None;
+        let mut dash = false;
+        let mut focus = false;
+        let mut bomb = false;
 
         for evt in self.events.drain(..) {
             match evt {
@@ -185,67 +669,375 @@ impl App {
                     player_move_y += y;
                 }
                 GameEvent::Shoot(x, y) => {
-                    shoot = Some((x, y));
+                    shoot = Some((x, y, Shot::Primary));
+                }
+                GameEvent::ShootSecondary(x, y) => {
+                    shoot = Some((x, y, Shot::Secondary));
                 }
+                GameEvent::Special(x, y) => {
+                    shoot = Some((x, y, Shot::Special));
+                }
+                GameEvent::CycleLoadout(direction) => {
+                    let count = self.player.loadouts.len().max(1) as isize;
+                    let next =
+                        (self.player.loadout as isize + direction as isize).rem_euclid(count);
+                    self.player.loadout = next as usize;
+                }
+                GameEvent::Cast(language) => {
+                    self.player.skills.push(Skill::new(language));
+                    if let Some(buff) = language.buff() {
+                        self.player.statuses.apply(buff);
+                    }
+                }
+                GameEvent::Charge => {
+                    if self.player.charge.is_none() && self.player.statuses.can_cast() {
+                        self.player.charge = Some(Duration::ZERO);
+                    }
+                }
+                GameEvent::CancelCharge => self.player.charge = None,
+                GameEvent::Dash => dash = true,
+                GameEvent::Focus => focus = true,
+                GameEvent::Bomb => bomb = true,
             }
         }
 
-        player_move_x *= self.player.move_velocity * delta.as_secs_f64();
-        player_move_y *= self.player.move_velocity * delta.as_secs_f64();
+        self.player.hp -= self.player.statuses.tick(delta);
+        self.stats.mp_used += self.player.charge(delta);
+        self.player.tick_timers(delta);
+
+        // several moves in one step shouldn't add up to a faster walk
+        let length = player_move_x.hypot(player_move_y);
+        if length > 1. {
+            player_move_x /= length;
+            player_move_y /= length;
+        }
+        if dash && self.player.start_dash(player_move_x, player_move_y) {
+            self.logs.push(GameLog("dash!".to_string()));
+        }
+        if bomb {
+            self.bomb();
+        }
+        self.player.focused = focus;
+        let speed_multiplier = self.player.statuses.speed_multiplier();
+        let dashing = self.player.dash.is_some();
+        if let Some(((x, y), remaining)) = self.player.dash {
+            // the dash takes over walking until it's done
+            let time = remaining.min(delta);
+            let distance = Player::DASH_SPEED * speed_multiplier * time.as_secs_f64();
+            player_move_x = x * distance;
+            player_move_y = y * distance;
+            self.player.dash = Some(((x, y), remaining - time)).filter(|(_, r)| !r.is_zero());
+        } else {
+            let mut velocity = self.player.move_velocity * speed_multiplier;
+            if focus {
+                velocity *= Player::FOCUS_SPEED;
+            }
+            player_move_x *= velocity * delta.as_secs_f64();
+            player_move_y *= velocity * delta.as_secs_f64();
+        }
         self.player.walk(player_move_x, player_move_y).unwrap();
+        (self.player.pos_x, self.player.pos_y) =
+            self.arena
+                .clamp(self.player.pos_x, self.player.pos_y, self.player.size);
+        for o in &self.obstacles {
+            (self.player.pos_x, self.player.pos_y) =
+                o.push_out(self.player.pos_x, self.player.pos_y, self.player.size);
+        }
+        if dashing {
+            self.player
+                .trail
+                .push((self.player.pos_x, self.player.pos_y));
+        }
 
-        if let Some((sx, sy)) = shoot {
-            // TODO: check skill
+        if let Some((sx, sy, shot)) = shoot {
+            // a spoken spell takes over the loadout
+            let loadout = self.player.loadout();
+            let slot = match shot {
+                Shot::Secondary => loadout.secondary,
+                Shot::Primary | Shot::Special => loadout.primary,
+            };
+            let language = self.player.skills.last().map(|s| s.language).or(slot);
+            let (count, cost) = match shot {
+                Shot::Special => (Player::SPECIAL_BULLETS, Player::SPECIAL_MP),
+                Shot::Primary | Shot::Secondary => (1, 1),
+            };
 
-            if self.player.mp <= 0 {
+            if !self.player.statuses.can_cast() {
+                self.logs.push(GameLog("cannot cast now".to_string()));
+            } else if self.player.mp < cost {
                 self.logs.push(GameLog("not enough MP".to_string()));
             } else {
-                let bullet = self.player.new_bullet(sx, sy);
-                self.bullets.push(bullet);
+                let bullet = self.player.new_bullet(sx, sy, language);
+                let first = -(count as f64 - 1.) / 2. * Player::SPECIAL_SPREAD;
+                for i in 0..count {
+                    self.spawn_bullet(bullet.rotated(first + i as f64 * Player::SPECIAL_SPREAD));
+                }
                 self.logs
                     .push(GameLog(format!("shoot pos=({:.2}, {:.2})", sx, sy)));
-                self.player.mp -= 1;
+                self.player.mp -= cost;
+                self.stats.mp_used += cost;
+                self.stats.shots += 1;
             }
 
             self.player.skills.clear();
+            self.player.charge = None;
         }
 
         // bullets
-        for b in &mut self.bullets {
-            b.pos_x += b.velocity_x * delta.as_secs_f64();
-            b.pos_y += b.velocity_y * delta.as_secs_f64();
+        for _ in 0..Self::BULLET_SUBSTEPS {
+            self.tick_bullets(delta / Self::BULLET_SUBSTEPS);
+        }
 
-            // check collision for player, enemy should do this in their own impl
-            if !b.is_player
-                && crate::dis(b.pos_x, b.pos_y, self.player.pos_x, self.player.pos_y)
-                    <= self.player.size
+        // obstacles
+        for o in &mut self.obstacles {
+            let damage = o.tick(delta);
+            if damage == 0 {
+                continue;
+            }
+            if !self.player.is_invulnerable()
+                && o.overlaps(
+                    self.player.pos_x,
+                    self.player.pos_y,
+                    self.player.hit_radius(),
+                )
             {
-                b.will_remove = true;
-                self.player.hp -= 1;
+                self.player.hp -= self.player.statuses.absorb(damage);
+            }
+            let (x, y) = self.enemy.pos();
+            if o.overlaps(x, y, 0.) {
+                self.enemy.take_damage(damage);
             }
         }
+        if self.obstacles.iter().any(|o| o.is_destroyed()) {
+            self.obstacles.retain(|o| !o.is_destroyed());
+            self.logs.push(GameLog("obstacle destroyed".to_string()));
+        }
 
         // enemy
         if matches!(
-            self.enemy.tick(delta, &mut self.player).unwrap(),
+            self.enemy
+                .tick(delta, &mut self.player, &mut self.rng)
+                .unwrap(),
             EnemyAction::Die
         ) {
             self.logs
                 .push(GameLog(format!("enemy {} died.", self.stage_index)));
-            self.stage_index += 1;
-            if let Some(e) = create_enemy(self.stage_index) {
-                self.enemy = e;
+            self.stats.stage_clears.push(self.steps);
+            self.camera.shake(3.);
+            if self.load_stage(self.stage_index + 1) {
                 // TODO: gain new skill
             } else {
-                // TODO: game ending
+                self.result = Some(GameResult::Cleared);
+                self.logs.push(GameLog("all stages cleared!".to_string()));
             }
             self.bullets.retain(|b| b.is_player);
         } else {
-            self.bullets.extend(self.enemy.bullets());
-            self.enemy.hurt(&mut self.bullets);
+            for b in self.enemy.bullets() {
+                self.spawn_bullet(b);
+            }
+        }
+
+        self.bullets.retain(|b| !b.will_remove);
+
+        let damage = (hp - self.player.hp).max(0);
+        self.stats.damage_taken += damage;
+        if damage > 0 {
+            self.camera.shake(damage as f64);
+        }
+
+        if self.player.hp <= 0 && self.result.is_none() {
+            self.result = Some(GameResult::Defeated);
+            self.logs.push(GameLog("you were defeated...".to_string()));
+        }
+    }
+
+    fn spawn_bullet(&mut self, mut bullet: Bullet) {
+        bullet.prev_x = bullet.pos_x;
+        bullet.prev_y = bullet.pos_y;
+        self.bullets.push(bullet);
+    }
+
+    /// Move bullets and resolve their collisions
+    fn tick_bullets(&mut self, delta: Duration) {
+        for b in &mut self.bullets {
+            b.prev_x = b.pos_x;
+            b.prev_y = b.pos_y;
+            b.pos_x += b.velocity_x * delta.as_secs_f64();
+            b.pos_y += b.velocity_y * delta.as_secs_f64();
+
+            self.arena.reflect(b);
+            if !self.arena.contains(b.pos_x, b.pos_y, Arena::CULL_MARGIN) {
+                b.will_remove = true;
+            }
+        }
+
+        // collision
+        self.grid.rebuild(&self.bullets);
+        let mut candidates = vec![];
+        for o in &mut self.obstacles {
+            self.grid.query(o.aabb(), &mut candidates);
+            for &i in &candidates {
+                o.block(&mut self.bullets[i]);
+            }
+        }
+
+        let (enemy_x, enemy_y) = self.enemy.pos();
+        let targets = [
+            (
+                Target::Player,
+                Collider::Circle {
+                    x: self.player.pos_x,
+                    y: self.player.pos_y,
+                    radius: self.player.hit_radius(),
+                },
+            ),
+            (
+                Target::Enemy,
+                Collider::Circle {
+                    x: enemy_x,
+                    y: enemy_y,
+                    radius: self.enemy.radius(),
+                },
+            ),
+        ];
+        for evt in collision::detect(&self.grid, &self.bullets, &targets) {
+            self.resolve_hit(evt);
+        }
+        self.graze();
+    }
+
+    /// Reward enemy bullets that passed close to the player without hitting
+    fn graze(&mut self) {
+        let player = &self.player;
+        let radius = player.hit_radius() + Player::GRAZE_DISTANCE;
+        let mut grazes = 0;
+        for b in self
+            .bullets
+            .iter_mut()
+            .filter(|b| !b.is_player && !b.will_remove)
+        {
+            // passing through while invulnerable isn't a graze
+            if player.is_invulnerable() {
+                b.grazed = false;
+            } else if crate::dis(b.pos_x, b.pos_y, player.pos_x, player.pos_y) <= radius {
+                b.grazed = true;
+            } else if b.grazed {
+                b.grazed = false;
+                grazes += 1;
+            }
+        }
+        if grazes == 0 {
+            return;
+        }
+
+        let stage = self.stage_index;
+        if self.stats.grazes.len() <= stage {
+            self.stats.grazes.resize(stage + 1, 0);
         }
+        self.stats.grazes[stage] += grazes;
+        self.player.mp =
+            (self.player.mp + grazes as isize * Player::GRAZE_MP).min(self.player.max_mp);
+    }
+
+    /// Clear enemy bullets around the player, turning them into MP
+    fn bomb(&mut self) {
+        if self.player.bombs == 0 {
+            self.logs.push(GameLog("no bombs left".to_string()));
+            return;
+        }
+        self.player.bombs -= 1;
+        self.stats.bombs_used += 1;
 
+        let (x, y) = (self.player.pos_x, self.player.pos_y);
+        let mut cleared = 0;
+        for b in self.bullets.iter_mut().filter(|b| !b.is_player) {
+            if crate::dis(b.pos_x, b.pos_y, x, y) <= Player::BOMB_RADIUS {
+                b.will_remove = true;
+                cleared += 1;
+            }
+        }
         self.bullets.retain(|b| !b.will_remove);
+
+        let mp = (cleared / Player::BOMB_BULLETS_PER_MP) as isize;
+        self.player.mp = (self.player.mp + mp).min(self.player.max_mp);
+        self.player.invulnerable = self.player.invulnerable.max(Player::BOMB_IFRAMES);
+        self.player.bomb_flash = Player::BOMB_FLASH_TIME;
+        self.camera.shake(2.);
+        self.logs
+            .push(GameLog(format!("bomb! cleared {cleared} bullets")));
+    }
+
+    /// Bullets grazed on the current stage
+    pub fn grazes(&self) -> usize {
+        self.stats
+            .grazes
+            .get(self.stage_index)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Apply the result of a bullet hitting something
+    fn resolve_hit(&mut self, evt: HitEvent) {
+        // bullets pass through an invulnerable player
+        if evt.target == Target::Player && self.player.is_invulnerable() {
+            return;
+        }
+        let b = &mut self.bullets[evt.bullet];
+        b.will_remove = true;
+
+        match evt.target {
+            Target::Player => {
+                let damage = self.player.statuses.absorb(1);
+                self.player.hp -= damage;
+                // a shield takes the whole hit, effect included
+                if damage > 0 {
+                    if let Some(effect) = b.effect {
+                        self.player.statuses.apply(effect);
+                    }
+                    self.player.invulnerable = Player::HIT_IFRAMES;
+                }
+            }
+            Target::Enemy => {
+                let hit = self.enemy.hurt(b);
+                if hit.damage >= Self::BIG_HIT {
+                    self.camera.shake(1.5);
+                }
+                match hit.affinity {
+                    Affinity::Weak => self
+                        .logs
+                        .push(GameLog(format!("super effective! {} damage", hit.damage))),
+                    Affinity::Resist => self
+                        .logs
+                        .push(GameLog(format!("resisted... {} damage", hit.damage))),
+                    Affinity::Immune => self.logs.push(GameLog("no effect".to_string())),
+                    Affinity::Normal => {}
+                }
+            }
+        }
+    }
+
+    /// Where a shot fired from the keyboard would go
+    pub fn aim_target(&self) -> (f64, f64) {
+        let player = &self.player;
+        // far out, so walking or dashing before the shot doesn't turn it around
+        let reach = self.arena.max_x - self.arena.min_x;
+        let facing = (
+            player.pos_x + player.face_x * reach,
+            player.pos_y + player.face_y * reach,
+        );
+        match self.aim {
+            AimMode::Facing => facing,
+            AimMode::Auto if self.result.is_none() => self.enemy.pos(),
+            AimMode::Auto => facing,
+            AimMode::Cursor => self.cursor,
+        }
+    }
+
+    /// Move the free-aim cursor, keeping it inside the arena
+    pub fn move_cursor(&mut self, delta_x: f64, delta_y: f64) {
+        self.cursor = self
+            .arena
+            .clamp(self.cursor.0 + delta_x, self.cursor.1 + delta_y, 0.);
     }
 
     /// Set running to false to quit the application.
@@ -253,51 +1045,94 @@ impl App {
         self.running = false;
     }
 
+    /// Handles text recognized from the microphone
+    pub fn on_speech(&mut self, text: &str) {
+        if text
+            .split_whitespace()
+            .any(|w| w.eq_ignore_ascii_case(Self::BOMB_WORD))
+        {
+            self.events.push(GameEvent::Bomb);
+        }
+    }
+
     pub fn on_mouse_event(&mut self, evt: MouseEvent) -> AppResult<()> {
         match evt.kind {
-            MouseEventKind::Down(_btn) => {
+            MouseEventKind::Moved | MouseEventKind::Drag(_) => {
+                self.hover = self.viewport.to_world(evt.column, evt.row);
+            }
+            MouseEventKind::Down(MouseButton::Left | MouseButton::Right) => {
                 self.casting = true;
                 self.logs.push(GameLog("casting...".to_string()));
+                self.events.push(GameEvent::Charge);
             }
-            MouseEventKind::Up(_btn) => {
-                let x_size = self.world_width;
-                let y_size = x_size
-                    * (self.canvas_rect.height as f64 / self.canvas_rect.width as f64)
-                    * App::CHAR_RATIO;
-
-                let x_bound = [-x_size, x_size];
-                let y_bound = [-y_size, y_size];
-                let x_grid_bound = [
-                    self.canvas_rect.x,
-                    self.canvas_rect.x + self.canvas_rect.width,
-                ];
-                let y_grid_bound = [
-                    self.canvas_rect.y,
-                    self.canvas_rect.y + self.canvas_rect.height,
-                ];
-
-                let grid_x = evt.column - self.canvas_rect.x;
-                let grid_y = evt.row - self.canvas_rect.y;
-
-                let click_x = crate::map_range(
-                    x_bound[0],
-                    x_bound[1],
-                    x_grid_bound[0] as f64,
-                    x_grid_bound[1] as f64,
-                    grid_x as f64,
-                );
-                let click_y = crate::map_range(
-                    y_bound[1],
-                    y_bound[0],
-                    y_grid_bound[0] as f64,
-                    y_grid_bound[1] as f64,
-                    grid_y as f64,
-                );
-                self.events.push(GameEvent::Shoot(click_x, click_y));
+            MouseEventKind::Down(MouseButton::Middle) => self.events.push(GameEvent::Bomb),
+            MouseEventKind::Up(btn) => {
+                self.casting = false;
+                let Some((x, y)) = self.viewport.to_world(evt.column, evt.row) else {
+                    // released off the canvas, don't keep draining MP
+                    self.events.push(GameEvent::CancelCharge);
+                    return Ok(());
+                };
+                match btn {
+                    MouseButton::Left if evt.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.events.push(GameEvent::Special(x, y))
+                    }
+                    MouseButton::Left => self.events.push(GameEvent::Shoot(x, y)),
+                    MouseButton::Right => self.events.push(GameEvent::ShootSecondary(x, y)),
+                    // the bomb went off on press
+                    MouseButton::Middle => {}
+                }
             }
+            MouseEventKind::ScrollUp => self.events.push(GameEvent::CycleLoadout(-1)),
+            MouseEventKind::ScrollDown => self.events.push(GameEvent::CycleLoadout(1)),
             _ => {}
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, DodgeBot};
+    use crate::observation::Observation;
+    use crate::status::StatusKind;
+
+    /// Final state of a bot playing from `seed`, as JSON so floats compare bit
+    /// for bit
+    fn play(seed: u64) -> String {
+        let mut app = App::with_seed(seed);
+        let mut bot = DodgeBot::new();
+        for _ in 0..900 {
+            app.events = bot.act(&Observation::new(&app));
+            app.step();
+        }
+        serde_json::to_string(&(Observation::new(&app), &app.stats)).unwrap()
+    }
+
+    #[test]
+    fn same_inputs_same_state() {
+        assert_eq!(play(7), play(7));
+    }
+
+    #[test]
+    fn shield_blocks_the_whole_hit() {
+        let mut app = App::new();
+        let shield = StatusEffect::new(StatusKind::Shield, Duration::from_secs(5));
+        app.player.statuses.apply(shield);
+        app.bullets.push(Bullet {
+            effect: Some(StatusEffect::new(StatusKind::Slow, Duration::from_secs(1))),
+            ..Default::default()
+        });
+        let hp = app.player.hp;
+
+        app.resolve_hit(HitEvent {
+            bullet: 0,
+            target: Target::Player,
+        });
+        assert_eq!(app.player.hp, hp);
+        assert!(!app.player.statuses.has(StatusKind::Slow));
+        assert!(!app.player.is_invulnerable());
+    }
+}
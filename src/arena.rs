@@ -0,0 +1,90 @@
+use ratatui::{
+    style::Color,
+    widgets::canvas::{self, Rectangle, Shape},
+};
+
+use serde::Serialize;
+
+use crate::app::Bullet;
+
+/// The rectangle where a battle takes place
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Arena {
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new(100., 50.)
+    }
+}
+
+impl Arena {
+    /// How far a bullet can travel outside the walls before being removed
+    pub const CULL_MARGIN: f64 = 10.;
+
+    /// Create an arena centered at the origin
+    pub fn new(half_width: f64, half_height: f64) -> Self {
+        Self {
+            min_x: -half_width,
+            max_x: half_width,
+            min_y: -half_height,
+            max_y: half_height,
+        }
+    }
+
+    /// Clamp a circle at (x, y) so it stays inside the walls
+    pub fn clamp(&self, x: f64, y: f64, radius: f64) -> (f64, f64) {
+        (
+            x.clamp(self.min_x + radius, self.max_x - radius),
+            y.clamp(self.min_y + radius, self.max_y - radius),
+        )
+    }
+
+    pub fn contains(&self, x: f64, y: f64, margin: f64) -> bool {
+        (self.min_x - margin..=self.max_x + margin).contains(&x)
+            && (self.min_y - margin..=self.max_y + margin).contains(&y)
+    }
+
+    /// Bounce a bullet off the walls it crossed, returns whether it bounced.
+    ///
+    /// A bullet only ricochets once, its flag is cleared after bouncing.
+    pub fn reflect(&self, bullet: &mut Bullet) -> bool {
+        if !bullet.ricochet {
+            return false;
+        }
+
+        let mut bounced = false;
+        if bullet.pos_x < self.min_x || bullet.pos_x > self.max_x {
+            bullet.pos_x = bullet.pos_x.clamp(self.min_x, self.max_x);
+            bullet.velocity_x = -bullet.velocity_x;
+            bounced = true;
+        }
+        if bullet.pos_y < self.min_y || bullet.pos_y > self.max_y {
+            bullet.pos_y = bullet.pos_y.clamp(self.min_y, self.max_y);
+            bullet.velocity_y = -bullet.velocity_y;
+            bounced = true;
+        }
+
+        if bounced {
+            bullet.ricochet = false;
+        }
+        bounced
+    }
+}
+
+impl Shape for Arena {
+    fn draw(&self, painter: &mut canvas::Painter) {
+        let rect = Rectangle {
+            x: self.min_x,
+            y: self.min_y,
+            width: self.max_x - self.min_x,
+            height: self.max_y - self.min_y,
+            color: Color::DarkGray,
+        };
+        rect.draw(painter);
+    }
+}
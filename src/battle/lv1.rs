@@ -1,18 +1,22 @@
 use crate::app::{AppResult, Bullet, Player};
-use crate::battle::{Enemy, EnemyAction};
+use crate::battle::{Affinity, Enemy, EnemyAction, Hit};
+use crate::rng::Rng;
+use crate::skill::Language;
+use crate::status::{StatusEffect, StatusEffects, StatusKind};
 use ratatui::{
     prelude::*,
     widgets::canvas::{Circle, Shape},
 };
 use std::{fmt::Debug, time::Duration};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EnemyLevel1 {
     pos_x: f64,
     pos_y: f64,
     hp: isize,
     timer: Duration,
     bullets: Vec<Bullet>,
+    statuses: StatusEffects,
 }
 
 impl EnemyLevel1 {
@@ -20,9 +24,10 @@ impl EnemyLevel1 {
         Self {
             pos_x: 30.,
             pos_y: 30.,
-            hp: 10,
+            hp: 20,
             timer: Duration::ZERO,
             bullets: vec![],
+            statuses: StatusEffects::default(),
         }
     }
 
@@ -40,6 +45,7 @@ impl EnemyLevel1 {
             velocity_x: delta_x * VELOCITY,
             velocity_y: delta_y * VELOCITY,
             is_player: false,
+            effect: Some(StatusEffect::new(StatusKind::Slow, Duration::from_secs(1))),
             ..Default::default()
         };
         let b1 = b0.rotated(30.);
@@ -54,21 +60,27 @@ impl Shape for EnemyLevel1 {
         let circle = Circle {
             x: self.pos_x,
             y: self.pos_y,
-            radius: 3.,
-            color: Color::Red,
+            radius: self.radius(),
+            color: self.statuses.tint().unwrap_or(Color::Red),
         };
         circle.draw(painter);
     }
 }
 
 impl Enemy for EnemyLevel1 {
-    fn tick(&mut self, delta: Duration, player: &mut Player) -> AppResult<EnemyAction> {
+    fn tick(
+        &mut self,
+        delta: Duration,
+        player: &mut Player,
+        _rng: &mut Rng,
+    ) -> AppResult<EnemyAction> {
+        self.hp -= self.statuses.tick(delta);
         if self.hp <= 0 {
             return Ok(EnemyAction::Die);
         }
 
-        self.timer += delta;
-        if self.timer > Duration::from_secs_f32(2.) {
+        self.timer += delta.mul_f64(self.statuses.speed_multiplier());
+        if self.timer > Duration::from_secs_f32(2.) && self.statuses.can_cast() {
             self.timer = Duration::ZERO;
             self.bullets.extend(self.new_bullets(player));
         }
@@ -76,16 +88,40 @@ impl Enemy for EnemyLevel1 {
         Ok(EnemyAction::Idle)
     }
 
+    fn pos(&self) -> (f64, f64) {
+        (self.pos_x, self.pos_y)
+    }
+
+    fn hp(&self) -> isize {
+        self.hp
+    }
+
+    fn take_damage(&mut self, damage: isize) {
+        self.hp -= damage;
+    }
+
     fn bullets(&mut self) -> Vec<Bullet> {
         self.bullets.drain(..).collect()
     }
 
-    fn hurt(&mut self, bullets: &mut Vec<Bullet>) {
-        for b in bullets {
-            if b.is_player && crate::dis(b.pos_x, b.pos_y, self.pos_x, self.pos_y) <= 3. {
-                b.will_remove = true;
-                self.hp -= 1;
-            }
+    fn clone_box(&self) -> Box<dyn Enemy> {
+        Box::new(self.clone())
+    }
+
+    fn hurt(&mut self, bullet: &Bullet) -> Hit {
+        let hit = self.hit_by(bullet);
+        self.hp -= hit.damage;
+        if let Some(effect) = hit.effect {
+            self.statuses.apply(effect);
+        }
+        hit
+    }
+
+    fn affinity(&self, language: Language) -> Affinity {
+        match language {
+            Language::Go => Affinity::Weak,
+            Language::C => Affinity::Resist,
+            _ => Affinity::Normal,
         }
     }
 }
@@ -6,6 +6,11 @@ mod lv2;
 pub use lv2::EnemyLevel2;
 
 use crate::app::{AppResult, Bullet, Player};
+use crate::arena::Arena;
+use crate::obstacle::{Obstacle, ObstacleKind, ObstacleShape};
+use crate::rng::Rng;
+use crate::skill::Language;
+use crate::status::StatusEffect;
 use ratatui::widgets::canvas::Shape;
 use std::{fmt::Debug, time::Duration};
 
@@ -18,19 +23,170 @@ pub(crate) fn create_enemy(stage: usize) -> Option<Box<dyn Enemy>> {
     }
 }
 
+fn create_obstacles(stage: usize) -> Vec<Obstacle> {
+    match stage {
+        1 => vec![
+            Obstacle::new(
+                ObstacleShape::Rect {
+                    x: 8.,
+                    y: 10.,
+                    width: 8.,
+                    height: 4.,
+                },
+                ObstacleKind::Wall,
+            )
+            .destructible(10),
+            Obstacle::new(
+                ObstacleShape::Rect {
+                    x: -30.,
+                    y: 5.,
+                    width: 4.,
+                    height: 16.,
+                },
+                ObstacleKind::Wall,
+            ),
+        ],
+        2 => vec![
+            Obstacle::new(
+                ObstacleShape::Circle {
+                    x: 25.,
+                    y: 5.,
+                    radius: 4.,
+                },
+                ObstacleKind::Mirror,
+            ),
+            Obstacle::new(
+                ObstacleShape::Rect {
+                    x: -50.,
+                    y: -30.,
+                    width: 30.,
+                    height: 12.,
+                },
+                ObstacleKind::Hazard { damage: 1 },
+            ),
+        ],
+        _ => vec![],
+    }
+}
+
+/// Everything needed to start a stage
+#[derive(Debug)]
+pub struct Stage {
+    pub enemy: Box<dyn Enemy>,
+    pub obstacles: Vec<Obstacle>,
+    pub arena: Arena,
+}
+
+pub(crate) fn create_stage(stage: usize) -> Option<Stage> {
+    Some(Stage {
+        enemy: create_enemy(stage)?,
+        obstacles: create_obstacles(stage),
+        arena: create_arena(stage),
+    })
+}
+
+fn create_arena(stage: usize) -> Arena {
+    match stage {
+        // bigger than the screen, the camera follows the player
+        2 => Arena::new(150., 75.),
+        _ => Arena::default(),
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum EnemyAction {
     Die,
     Idle,
 }
 
+/// How an enemy reacts to spells written in a specific language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    Weak,
+    Normal,
+    Resist,
+    Immune,
+}
+
+impl Affinity {
+    pub fn apply(self, damage: isize) -> isize {
+        match self {
+            Affinity::Weak => damage * 2,
+            Affinity::Normal => damage,
+            // still hurts a little, unlike immune
+            Affinity::Resist => (damage / 2).max(1),
+            Affinity::Immune => 0,
+        }
+    }
+
+    /// Status effects last half as long when resisted, and don't land at all
+    /// when immune
+    pub fn apply_effect(self, effect: StatusEffect) -> Option<StatusEffect> {
+        match self {
+            Affinity::Weak | Affinity::Normal => Some(effect),
+            Affinity::Resist => Some(StatusEffect::stacked(
+                effect.kind,
+                effect.remaining / 2,
+                effect.stacks,
+            )),
+            Affinity::Immune => None,
+        }
+    }
+}
+
+/// A player bullet landed on an enemy
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    pub damage: isize,
+    pub affinity: Affinity,
+    /// Status effect to apply, after the affinity
+    pub effect: Option<StatusEffect>,
+}
+
 pub trait Enemy: Debug + Shape {
-    fn tick(&mut self, delta: Duration, app: &mut Player) -> AppResult<EnemyAction>;
+    fn tick(&mut self, delta: Duration, app: &mut Player, rng: &mut Rng) -> AppResult<EnemyAction>;
+
+    fn pos(&self) -> (f64, f64);
+
+    fn hp(&self) -> isize;
+
+    fn radius(&self) -> f64 {
+        3.
+    }
 
-    fn hurt(&mut self, bullets: &mut Vec<Bullet>);
+    /// Take damage not coming from bullets, e.g. hazard zones
+    fn take_damage(&mut self, damage: isize);
+
+    /// Apply the damage of a player bullet hitting this enemy
+    fn hurt(&mut self, bullet: &Bullet) -> Hit;
+
+    /// Resistance / weakness against spells written in `language`
+    fn affinity(&self, _language: Language) -> Affinity {
+        Affinity::Normal
+    }
+
+    /// Resolve the damage `bullet` would deal to this enemy
+    fn hit_by(&self, bullet: &Bullet) -> Hit {
+        let affinity = bullet
+            .language
+            .map_or(Affinity::Normal, |l| self.affinity(l));
+        Hit {
+            damage: affinity.apply(bullet.damage),
+            affinity,
+            effect: bullet.effect.and_then(|e| affinity.apply_effect(e)),
+        }
+    }
 
     /// Get bullets generated by this enemy
     fn bullets(&mut self) -> Vec<Bullet>;
+
+    fn clone_box(&self) -> Box<dyn Enemy>;
+}
+
+impl Clone for Box<dyn Enemy> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
 }
 
 pub struct DrawEnemy<'a>(pub &'a Box<dyn Enemy>);
@@ -40,3 +196,34 @@ impl Shape for DrawEnemy<'_> {
         self.0.draw(painter);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::status::StatusEffects;
+
+    #[test]
+    fn affinities_scale_a_plain_shot() {
+        let base = App::new().player.new_bullet(1., 0., None).damage;
+        let damage = |a: Affinity| a.apply(base);
+        assert!(damage(Affinity::Weak) > damage(Affinity::Normal));
+        assert!(damage(Affinity::Normal) > damage(Affinity::Resist));
+        assert!(damage(Affinity::Resist) > damage(Affinity::Immune));
+    }
+
+    #[test]
+    fn resisted_burn_does_less_damage() {
+        let burn = Language::C.effect().unwrap();
+        let total = |a: Affinity| {
+            let mut statuses = StatusEffects::default();
+            if let Some(effect) = a.apply_effect(burn) {
+                statuses.apply(effect);
+            }
+            statuses.tick(burn.remaining)
+        };
+        assert!(total(Affinity::Normal) > total(Affinity::Resist));
+        assert!(total(Affinity::Resist) > 0);
+        assert_eq!(total(Affinity::Immune), 0);
+    }
+}
@@ -0,0 +1,149 @@
+//! Let the reference bot play every stage many times and report how hard
+//! each one is.
+//!
+//! ```text
+//! balance [--games <n>] [--stage <n>] [--seed <n>] [--max-steps <n>] [--json]
+//! ```
+//!
+//! Game `i` of a stage uses seed `seed + i`, so runs are reproducible.
+
+use normal_game_jam_2024::app::{App, AppResult};
+use normal_game_jam_2024::bot::{Bot, DodgeBot};
+use normal_game_jam_2024::observation::Observation;
+use serde::Serialize;
+
+/// Two minutes of in-game time per stage
+const DEFAULT_MAX_STEPS: u64 = 30 * 60 * 2;
+
+#[derive(Debug)]
+struct Args {
+    games: u64,
+    stage: Option<usize>,
+    seed: u64,
+    max_steps: u64,
+    json: bool,
+}
+
+impl Args {
+    fn parse() -> AppResult<Self> {
+        let mut ret = Self {
+            games: 20,
+            stage: None,
+            seed: 0,
+            max_steps: DEFAULT_MAX_STEPS,
+            json: false,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+            match arg.as_str() {
+                "--games" => ret.games = value()?.parse()?,
+                "--stage" => ret.stage = Some(value()?.parse()?),
+                "--seed" => ret.seed = value()?.parse()?,
+                "--max-steps" => ret.max_steps = value()?.parse()?,
+                "--json" => ret.json = true,
+                _ => return Err(format!("unknown argument {arg}").into()),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// Aggregated results of one stage
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    stage: usize,
+    games: u64,
+    clears: u64,
+    clear_rate: f64,
+    /// Average seconds to kill the enemy, over cleared games
+    time_to_kill: Option<f64>,
+    /// Average over all games
+    damage_taken: f64,
+    mp_used: f64,
+    grazes: f64,
+}
+
+/// Play `stage` until it's cleared, the bot dies or time runs out
+fn play(stage: usize, seed: u64, max_steps: u64) -> App {
+    let mut app = App::with_seed(seed);
+    app.load_stage(stage);
+    let mut bot = DodgeBot::new();
+    while app.result.is_none() && app.stats.stage_clears.is_empty() && app.steps < max_steps {
+        app.events = bot.act(&Observation::new(&app));
+        app.step();
+    }
+    app
+}
+
+fn report(stage: usize, args: &Args) -> Report {
+    let mut report = Report {
+        stage,
+        games: args.games,
+        ..Default::default()
+    };
+    let mut kill_steps = 0;
+
+    for i in 0..args.games {
+        let app = play(stage, args.seed + i, args.max_steps);
+        if let Some(&step) = app.stats.stage_clears.first() {
+            report.clears += 1;
+            kill_steps += step + 1;
+        }
+        report.damage_taken += app.stats.damage_taken as f64;
+        report.mp_used += app.stats.mp_used as f64;
+        report.grazes += app.stats.grazes.get(stage).copied().unwrap_or(0) as f64;
+    }
+
+    let games = args.games.max(1) as f64;
+    report.clear_rate = report.clears as f64 / games;
+    report.damage_taken /= games;
+    report.mp_used /= games;
+    report.grazes /= games;
+    if report.clears > 0 {
+        report.time_to_kill =
+            Some(kill_steps as f64 / report.clears as f64 * App::FIXED_STEP.as_secs_f64());
+    }
+    report
+}
+
+fn main() -> AppResult<()> {
+    let args = Args::parse()?;
+
+    let stages: Vec<_> = (0..).take_while(|&s| App::new().load_stage(s)).collect();
+    let stages = match args.stage {
+        Some(stage) if !stages.contains(&stage) => {
+            return Err(format!("no stage {stage}").into());
+        }
+        Some(stage) => vec![stage],
+        None => stages,
+    };
+
+    let reports: Vec<_> = stages.into_iter().map(|s| report(s, &args)).collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:>5}  {:>5}  {:>10}  {:>13}  {:>12}  {:>7}  {:>6}",
+        "stage", "games", "clear rate", "time to kill", "damage taken", "MP used", "grazes"
+    );
+    for r in &reports {
+        let ttk = r
+            .time_to_kill
+            .map_or("-".to_string(), |t| format!("{t:.1}s"));
+        println!(
+            "{:>5}  {:>5}  {:>9.0}%  {:>13}  {:>12.1}  {:>7.1}  {:>6.1}",
+            r.stage,
+            r.games,
+            r.clear_rate * 100.,
+            ttk,
+            r.damage_taken,
+            r.mp_used,
+            r.grazes
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! Run the game without a terminal and print a JSON summary of the outcome.
+//!
+//! ```text
+//! headless [--seed <n>] [--replay <path> | --script <path>] [--max-steps <n>]
+//! headless --stdio [--seed <n>]
+//! ```
+//!
+//! A script is a replay file without its header, one `<step> <event>` per line.
+//!
+//! With `--stdio` the game is driven by an external program instead, see
+//! [`headless::serve`] for the protocol.
+
+use normal_game_jam_2024::app::{App, AppResult};
+use normal_game_jam_2024::headless;
+use normal_game_jam_2024::replay::Replay;
+use std::path::PathBuf;
+
+/// Ten minutes of in-game time
+const DEFAULT_MAX_STEPS: u64 = 30 * 60 * 10;
+
+#[derive(Debug, Default)]
+struct Args {
+    seed: u64,
+    replay: Option<PathBuf>,
+    script: Option<PathBuf>,
+    max_steps: Option<u64>,
+    stdio: bool,
+}
+
+impl Args {
+    fn parse() -> AppResult<Self> {
+        let mut ret = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+            match arg.as_str() {
+                "--seed" => ret.seed = value()?.parse()?,
+                "--replay" => ret.replay = Some(value()?.into()),
+                "--script" => ret.script = Some(value()?.into()),
+                "--max-steps" => ret.max_steps = Some(value()?.parse()?),
+                "--stdio" => ret.stdio = true,
+                _ => return Err(format!("unknown argument {arg}").into()),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+fn main() -> AppResult<()> {
+    let args = Args::parse()?;
+
+    if args.stdio {
+        let stdin = std::io::stdin().lock();
+        let stdout = std::io::stdout().lock();
+        return headless::serve(App::with_seed(args.seed), stdin, stdout);
+    }
+
+    let replay = match (&args.replay, &args.script) {
+        (Some(_), Some(_)) => return Err("--replay and --script can't be used together".into()),
+        (Some(path), None) => Some(Replay::load(path)?),
+        (None, Some(path)) => Some(Replay::load_script(path, args.seed)?),
+        (None, None) => None,
+    };
+
+    let (app, max_steps) = match replay {
+        // a replay stops where the recording stopped
+        Some(replay) => {
+            let length = replay.length;
+            (App::from_replay(replay), args.max_steps.unwrap_or(length))
+        }
+        None => (
+            App::with_seed(args.seed),
+            args.max_steps.unwrap_or(DEFAULT_MAX_STEPS),
+        ),
+    };
+
+    let summary = headless::run(app, max_steps);
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
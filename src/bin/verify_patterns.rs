@@ -0,0 +1,76 @@
+//! Check that every stage's attack pattern can be dodged, exits with an
+//! error if some can't.
+//!
+//! ```text
+//! verify_patterns [--stage <n>] [--seeds <n>] [--seconds <n>] [--json]
+//! ```
+
+use std::time::Duration;
+
+use normal_game_jam_2024::app::AppResult;
+use normal_game_jam_2024::dodge::{self, DodgeConfig};
+
+#[derive(Debug, Default)]
+struct Args {
+    stage: Option<usize>,
+    config: DodgeConfig,
+    json: bool,
+}
+
+impl Args {
+    fn parse() -> AppResult<Self> {
+        let mut ret = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+            match arg.as_str() {
+                "--stage" => ret.stage = Some(value()?.parse()?),
+                "--seeds" => ret.config.seeds = value()?.parse()?,
+                "--seconds" => ret.config.duration = Duration::from_secs_f64(value()?.parse()?),
+                "--json" => ret.json = true,
+                _ => return Err(format!("unknown argument {arg}").into()),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+fn main() -> AppResult<()> {
+    let args = Args::parse()?;
+
+    let reports: Vec<_> = match args.stage {
+        Some(stage) => {
+            vec![dodge::verify(stage, &args.config).ok_or(format!("no stage {stage}"))?]
+        }
+        None => (0..)
+            .map_while(|stage| dodge::verify(stage, &args.config))
+            .collect(),
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for r in &reports {
+            if r.is_dodgeable() {
+                println!("stage {}: ok", r.stage);
+                continue;
+            }
+            println!(
+                "stage {}: {} unavoidable moments",
+                r.stage,
+                r.unavoidable.len()
+            );
+            for u in &r.unavoidable {
+                println!(
+                    "  seed {} at {:.2}s near ({:.1}, {:.1}), {} cells reachable before",
+                    u.seed, u.time, u.x, u.y, u.region
+                );
+            }
+        }
+    }
+
+    if reports.iter().any(|r| !r.is_dodgeable()) {
+        return Err("some patterns can't be dodged".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::app::GameEvent;
+use crate::observation::Observation;
+
+/// Something that plays the game, every step it looks at the world and
+/// decides which inputs to give.
+pub trait Bot {
+    fn act(&mut self, obs: &Observation) -> Vec<GameEvent>;
+}
+
+/// A reference bot: moves to wherever bullets will be furthest away soon,
+/// and shoots the nearest enemy every now and then.
+#[derive(Debug, Clone)]
+pub struct DodgeBot {
+    /// Steps until the next shot
+    cooldown: u32,
+}
+
+impl Default for DodgeBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DodgeBot {
+    /// Movement directions tried every step, besides standing still
+    const DIRECTIONS: usize = 16;
+    /// How far ahead bullets are predicted, in seconds
+    const HORIZON: f64 = 0.5;
+    const SAMPLES: usize = 5;
+    /// Steps between shots
+    const SHOOT_INTERVAL: u32 = 10;
+    /// Distance from the walls the bot tries to keep
+    const WALL_MARGIN: f64 = 8.;
+
+    pub fn new() -> Self {
+        Self { cooldown: 0 }
+    }
+
+    /// Lower is safer
+    fn danger(obs: &Observation, x: f64, y: f64) -> f64 {
+        let player = &obs.player;
+        let mut danger = 0.;
+
+        for b in obs.bullets.iter().filter(|b| !b.is_player) {
+            for i in 1..=Self::SAMPLES {
+                let t = Self::HORIZON * i as f64 / Self::SAMPLES as f64;
+                let bx = b.x + b.velocity_x * t;
+                let by = b.y + b.velocity_y * t;
+                let d = crate::dis(x, y, bx, by) - player.radius;
+                danger += 1. / d.max(0.1).powi(2);
+            }
+        }
+
+        let arena = &obs.arena;
+        for wall in [
+            x - arena.min_x,
+            arena.max_x - x,
+            y - arena.min_y,
+            arena.max_y - y,
+        ] {
+            if wall < Self::WALL_MARGIN {
+                danger += (Self::WALL_MARGIN - wall) * 0.05;
+            }
+        }
+        danger
+    }
+}
+
+impl Bot for DodgeBot {
+    fn act(&mut self, obs: &Observation) -> Vec<GameEvent> {
+        let player = &obs.player;
+        let mut events = vec![];
+
+        // where we'd be after half the horizon in each direction
+        let reach = player.move_velocity * Self::HORIZON / 2.;
+        let mut best = (0., 0.);
+        let mut best_danger = Self::danger(obs, player.x, player.y);
+        for i in 0..Self::DIRECTIONS {
+            let angle = std::f64::consts::TAU * i as f64 / Self::DIRECTIONS as f64;
+            let (dx, dy) = (angle.cos(), angle.sin());
+            let danger = Self::danger(obs, player.x + dx * reach, player.y + dy * reach);
+            if danger < best_danger {
+                best = (dx, dy);
+                best_danger = danger;
+            }
+        }
+        if best != (0., 0.) {
+            events.push(GameEvent::PlayerMove(best.0, best.1));
+        }
+
+        self.cooldown = self.cooldown.saturating_sub(1);
+        let nearest = obs.enemies.iter().filter(|e| e.hp > 0).min_by(|a, b| {
+            crate::dis(player.x, player.y, a.x, a.y)
+                .total_cmp(&crate::dis(player.x, player.y, b.x, b.y))
+        });
+        if let Some(enemy) = nearest {
+            if self.cooldown == 0 && player.mp > 0 {
+                events.push(GameEvent::Shoot(enemy.x, enemy.y));
+                self.cooldown = Self::SHOOT_INTERVAL;
+            }
+        }
+
+        events
+    }
+}
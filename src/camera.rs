@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use ratatui::layout::Rect;
+
+use crate::arena::Arena;
+use crate::rng::Rng;
+use crate::viewport::Viewport;
+
+/// Decides which part of the world is shown.
+///
+/// Follows a target once it leaves a dead-zone around the center, never
+/// shows past the arena walls when the arena is bigger than the view, and
+/// shakes on big hits. It has its own random numbers so shaking doesn't
+/// change gameplay.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub x: f64,
+    pub y: f64,
+    /// Position at the start of the last simulation step
+    prev_x: f64,
+    prev_y: f64,
+    /// World units shown either side of the center at zoom 1
+    pub half_width: f64,
+    pub zoom: f64,
+    /// How far the target can be from the center before the camera moves, at
+    /// zoom 1
+    pub dead_zone: (f64, f64),
+    /// Current shake amplitude in world units, and how long it lasts
+    shake: f64,
+    shake_remaining: Duration,
+    shake_offset: (f64, f64),
+    rng: Rng,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            x: 0.,
+            y: 0.,
+            prev_x: 0.,
+            prev_y: 0.,
+            half_width: 100.,
+            zoom: 1.,
+            dead_zone: (20., 10.),
+            shake: 0.,
+            shake_remaining: Duration::ZERO,
+            shake_offset: (0., 0.),
+            rng: Rng::new(0),
+        }
+    }
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f64 = 0.5;
+    pub const MAX_ZOOM: f64 = 4.;
+    const ZOOM_STEP: f64 = 1.25;
+    const SHAKE_TIME: Duration = Duration::from_millis(300);
+    /// Strongest shake, in world units
+    const MAX_SHAKE: f64 = 3.;
+
+    /// Jump straight to (x, y), without following
+    pub fn reset(&mut self, x: f64, y: f64) {
+        (self.x, self.y) = (x, y);
+        (self.prev_x, self.prev_y) = (x, y);
+    }
+
+    /// Advance one step, moving just enough to keep (x, y) in the dead-zone
+    pub fn follow(&mut self, x: f64, y: f64, delta: Duration) {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
+        // shrinks with the view when zooming in
+        let (dead_x, dead_y) = (self.dead_zone.0 / self.zoom, self.dead_zone.1 / self.zoom);
+        self.x = self.x.clamp(x - dead_x, x + dead_x);
+        self.y = self.y.clamp(y - dead_y, y + dead_y);
+
+        self.shake_remaining = self.shake_remaining.saturating_sub(delta);
+        if self.shake_remaining.is_zero() {
+            self.shake = 0.;
+            self.shake_offset = (0., 0.);
+        } else {
+            let fade = self.shake_remaining.as_secs_f64() / Self::SHAKE_TIME.as_secs_f64();
+            let angle = self.rng.range(0., std::f64::consts::TAU);
+            let amplitude = self.shake * fade;
+            self.shake_offset = (angle.cos() * amplitude, angle.sin() * amplitude);
+        }
+    }
+
+    /// Shake by `amount` world units, stronger shakes take over weaker ones
+    pub fn shake(&mut self, amount: f64) {
+        self.shake = self.shake.max(amount).min(Self::MAX_SHAKE);
+        self.shake_remaining = Self::SHAKE_TIME;
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom * Self::ZOOM_STEP).min(Self::MAX_ZOOM);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom / Self::ZOOM_STEP).max(Self::MIN_ZOOM);
+    }
+
+    /// What to show in `area`, `alpha` is how far rendering is into the next
+    /// step
+    pub fn viewport(&self, area: Rect, arena: &Arena, alpha: f64) -> Viewport {
+        let x = crate::lerp(self.prev_x, self.x, alpha);
+        let y = crate::lerp(self.prev_y, self.y, alpha);
+        let half_width = self.half_width / self.zoom;
+        let view = Viewport::new(area, (x, y), half_width);
+        let half_height = view.height() / 2.;
+
+        // stay inside the walls, or centered on the arena if it's smaller
+        let clamp = |c: f64, half: f64, min: f64, max: f64| {
+            if max - min <= half * 2. {
+                (min + max) / 2.
+            } else {
+                c.clamp(min + half, max - half)
+            }
+        };
+        let x = clamp(x, half_width, arena.min_x, arena.max_x) + self.shake_offset.0;
+        let y = clamp(y, half_height, arena.min_y, arena.max_y) + self.shake_offset.1;
+        Viewport::new(area, (x, y), half_width)
+    }
+}
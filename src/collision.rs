@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use crate::app::Bullet;
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Aabb {
+    pub fn from_circle(x: f64, y: f64, radius: f64) -> Self {
+        Self {
+            min_x: x - radius,
+            min_y: y - radius,
+            max_x: x + radius,
+            max_y: y + radius,
+        }
+    }
+
+    /// Box around a circle swept from (x0, y0) to (x1, y1)
+    pub fn from_segment(x0: f64, y0: f64, x1: f64, y1: f64, radius: f64) -> Self {
+        Self {
+            min_x: x0.min(x1) - radius,
+            min_y: y0.min(y1) - radius,
+            max_x: x0.max(x1) + radius,
+            max_y: y0.max(y1) + radius,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Collider {
+    Circle { x: f64, y: f64, radius: f64 },
+    Aabb(Aabb),
+}
+
+impl Collider {
+    pub fn aabb(&self) -> Aabb {
+        match *self {
+            Collider::Circle { x, y, radius } => Aabb::from_circle(x, y, radius),
+            Collider::Aabb(aabb) => aabb,
+        }
+    }
+
+    /// Narrowphase test against a circle at (x, y)
+    pub fn intersects_circle(&self, x: f64, y: f64, radius: f64) -> bool {
+        match *self {
+            Collider::Circle {
+                x: cx,
+                y: cy,
+                radius: r,
+            } => crate::dis(x, y, cx, cy) <= r + radius,
+            Collider::Aabb(aabb) => {
+                let px = x.clamp(aabb.min_x, aabb.max_x);
+                let py = y.clamp(aabb.min_y, aabb.max_y);
+                crate::dis(x, y, px, py) <= radius
+            }
+        }
+    }
+
+    /// Continuous test for a circle moving from (x0, y0) to (x1, y1),
+    /// returns the earliest fraction of the movement in `[0, 1]` where they touch.
+    pub fn sweep_circle(&self, x0: f64, y0: f64, x1: f64, y1: f64, radius: f64) -> Option<f64> {
+        let dx = x1 - x0;
+        let dy = y1 - y0;
+
+        match *self {
+            Collider::Circle {
+                x: cx,
+                y: cy,
+                radius: r,
+            } => {
+                // solve |p0 + t * d - c| = r for t
+                let r = r + radius;
+                let fx = x0 - cx;
+                let fy = y0 - cy;
+                let c = fx * fx + fy * fy - r * r;
+                if c <= 0. {
+                    return Some(0.);
+                }
+
+                let a = dx * dx + dy * dy;
+                let b = 2. * (fx * dx + fy * dy);
+                let discriminant = b * b - 4. * a * c;
+                if a == 0. || discriminant < 0. {
+                    return None;
+                }
+
+                let t = (-b - discriminant.sqrt()) / (2. * a);
+                (0. ..=1.).contains(&t).then_some(t)
+            }
+            Collider::Aabb(aabb) => {
+                // slab test against the box grown by the radius
+                let mut t_min: f64 = 0.;
+                let mut t_max: f64 = 1.;
+                for (p, d, min, max) in [
+                    (x0, dx, aabb.min_x - radius, aabb.max_x + radius),
+                    (y0, dy, aabb.min_y - radius, aabb.max_y + radius),
+                ] {
+                    if d == 0. {
+                        if p < min || p > max {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let t0 = (min - p) / d;
+                    let t1 = (max - p) / d;
+                    t_min = t_min.max(t0.min(t1));
+                    t_max = t_max.min(t0.max(t1));
+                    if t_min > t_max {
+                        return None;
+                    }
+                }
+                Some(t_min)
+            }
+        }
+    }
+}
+
+/// Uniform grid broadphase, bullets are bucketed by the cell they're in
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(8.)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, x: f64, y: f64) -> (i64, i64) {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+        )
+    }
+
+    /// Clear the grid but keep the allocated buckets around
+    pub fn clear(&mut self) {
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+    }
+
+    pub fn insert(&mut self, id: usize, aabb: Aabb) {
+        let (x0, y0) = self.cell(aabb.min_x, aabb.min_y);
+        let (x1, y1) = self.cell(aabb.max_x, aabb.max_y);
+        for cx in x0..=x1 {
+            for cy in y0..=y1 {
+                self.cells.entry((cx, cy)).or_default().push(id);
+            }
+        }
+    }
+
+    /// Rebuild the grid from live bullets, ids are indices into `bullets`.
+    ///
+    /// Bullets are inserted with the whole path they moved in the last step.
+    pub fn rebuild(&mut self, bullets: &[Bullet]) {
+        self.clear();
+        for (i, b) in bullets.iter().enumerate() {
+            if !b.will_remove {
+                self.insert(
+                    i,
+                    Aabb::from_segment(b.prev_x, b.prev_y, b.pos_x, b.pos_y, 0.),
+                );
+            }
+        }
+    }
+
+    /// Collect ids which may overlap `aabb` into `out`, sorted and deduplicated
+    pub fn query(&self, aabb: Aabb, out: &mut Vec<usize>) {
+        out.clear();
+        let (x0, y0) = self.cell(aabb.min_x, aabb.min_y);
+        let (x1, y1) = self.cell(aabb.max_x, aabb.max_y);
+        for cx in x0..=x1 {
+            for cy in y0..=y1 {
+                if let Some(bucket) = self.cells.get(&(cx, cy)) {
+                    out.extend_from_slice(bucket);
+                }
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+    }
+}
+
+/// Something bullets can hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Player,
+    Enemy,
+}
+
+impl Target {
+    /// Bullets never hit whoever fired them
+    fn is_hit_by(&self, bullet: &Bullet) -> bool {
+        match self {
+            Target::Player => !bullet.is_player,
+            Target::Enemy => bullet.is_player,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HitEvent {
+    /// Index into the bullet list the grid was built from
+    pub bullet: usize,
+    pub target: Target,
+}
+
+/// Find bullets hitting targets along the path they moved in the last step,
+/// so fast bullets can't tunnel through. Each bullet hits at most one target,
+/// the first one on its path.
+///
+/// `grid` must be built from `bullets` with [`SpatialHash::rebuild`].
+pub fn detect(
+    grid: &SpatialHash,
+    bullets: &[Bullet],
+    targets: &[(Target, Collider)],
+) -> Vec<HitEvent> {
+    let mut candidates = vec![];
+    // earliest hit of each bullet
+    let mut first: Vec<Option<(f64, Target)>> = vec![None; bullets.len()];
+
+    for (target, collider) in targets {
+        grid.query(collider.aabb(), &mut candidates);
+        for &i in &candidates {
+            let b = &bullets[i];
+            if b.will_remove || !target.is_hit_by(b) {
+                continue;
+            }
+            let Some(t) = collider.sweep_circle(b.prev_x, b.prev_y, b.pos_x, b.pos_y, 0.) else {
+                continue;
+            };
+            match first[i] {
+                Some((t0, _)) if t0 <= t => {}
+                _ => first[i] = Some((t, *target)),
+            }
+        }
+    }
+
+    first
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, hit)| hit.map(|(_, target)| HitEvent { bullet: i, target }))
+        .collect()
+}
@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::app::{App, Bullet};
+use crate::arena::Arena;
+
+/// How a pattern is checked
+#[derive(Debug, Clone)]
+pub struct DodgeConfig {
+    /// Length of the attack timeline simulated
+    pub duration: Duration,
+    /// Each seed is a different roll of the enemy's randomness
+    pub seeds: u64,
+    /// Side of a cell of the position grid
+    pub cell_size: f64,
+}
+
+impl Default for DodgeConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(20),
+            seeds: 8,
+            cell_size: 1.,
+        }
+    }
+}
+
+/// A moment no position reachable by the player was safe
+#[derive(Debug, Clone, Serialize)]
+pub struct Unavoidable {
+    pub seed: u64,
+    /// Seconds into the stage
+    pub time: f64,
+    /// Where the player could still be right before, the reachable cell
+    /// closest to the middle of that region
+    pub x: f64,
+    pub y: f64,
+    /// Cells the player could still reach right before
+    pub region: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DodgeReport {
+    pub stage: usize,
+    pub unavoidable: Vec<Unavoidable>,
+}
+
+impl DodgeReport {
+    pub fn is_dodgeable(&self) -> bool {
+        self.unavoidable.is_empty()
+    }
+}
+
+/// Positions of the arena the player could be at, one bit per cell
+struct Grid {
+    arena: Arena,
+    cell_size: f64,
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    fn new(arena: Arena, cell_size: f64) -> Self {
+        let width = ((arena.max_x - arena.min_x) / cell_size).ceil() as usize;
+        let height = ((arena.max_y - arena.min_y) / cell_size).ceil() as usize;
+        Self {
+            arena,
+            cell_size,
+            width,
+            height,
+            cells: vec![false; width * height],
+        }
+    }
+
+    fn center(&self, i: usize) -> (f64, f64) {
+        (
+            self.arena.min_x + ((i % self.width) as f64 + 0.5) * self.cell_size,
+            self.arena.min_y + ((i / self.width) as f64 + 0.5) * self.cell_size,
+        )
+    }
+
+    fn cell(&self, x: f64, y: f64) -> usize {
+        let col = ((x - self.arena.min_x) / self.cell_size) as usize;
+        let row = ((y - self.arena.min_y) / self.cell_size) as usize;
+        row.min(self.height - 1) * self.width + col.min(self.width - 1)
+    }
+
+    /// Mark every cell whose center is within `radius` of (x, y)
+    fn stamp(&mut self, x: f64, y: f64, radius: f64) {
+        let reach = (radius / self.cell_size).ceil() as isize + 1;
+        let col = ((x - self.arena.min_x) / self.cell_size).floor() as isize;
+        let row = ((y - self.arena.min_y) / self.cell_size).floor() as isize;
+        for r in row - reach..=row + reach {
+            for c in col - reach..=col + reach {
+                if r < 0 || c < 0 || r >= self.height as isize || c >= self.width as isize {
+                    continue;
+                }
+                let i = r as usize * self.width + c as usize;
+                let (cx, cy) = self.center(i);
+                if crate::dis(x, y, cx, cy) <= radius {
+                    self.cells[i] = true;
+                }
+            }
+        }
+    }
+
+    /// Grow the set by one cell in each axis direction
+    fn dilate(&self) -> Vec<bool> {
+        let mut next = self.cells.clone();
+        for (i, _) in self.cells.iter().enumerate().filter(|(_, &c)| c) {
+            let (col, row) = (i % self.width, i / self.width);
+            if col > 0 {
+                next[i - 1] = true;
+            }
+            if col + 1 < self.width {
+                next[i + 1] = true;
+            }
+            if row > 0 {
+                next[i - self.width] = true;
+            }
+            if row + 1 < self.height {
+                next[i + self.width] = true;
+            }
+        }
+        next
+    }
+}
+
+/// Check whether a player can survive `stage`'s attacks without being hit.
+///
+/// The enemy's attacks are simulated without the player fighting back, with
+/// aimed shots fired at a player standing still at the spawn point. Starting
+/// from the spawn point, the set of cells the player can reach without being
+/// hit is grown by one cell every slice of time it takes to walk one cell,
+/// and cells a bullet passes within `Player.size` of, or inside an obstacle,
+/// are removed. Every time that set runs empty is unavoidable; the player is
+/// then assumed to have tanked the hit and analysis goes on.
+///
+/// Returns `None` if there's no such stage.
+pub fn verify(stage: usize, config: &DodgeConfig) -> Option<DodgeReport> {
+    let mut report = DodgeReport {
+        stage,
+        unavoidable: vec![],
+    };
+    for seed in 0..config.seeds {
+        report.unavoidable.extend(verify_seed(stage, seed, config)?);
+    }
+    Some(report)
+}
+
+fn verify_seed(stage: usize, seed: u64, config: &DodgeConfig) -> Option<Vec<Unavoidable>> {
+    let mut app = App::with_seed(seed);
+    if !app.load_stage(stage) {
+        return None;
+    }
+    let delta = App::FIXED_STEP;
+    let radius = app.player.size;
+    // steps it takes to walk one cell
+    let slice = (config.cell_size / (app.player.move_velocity * delta.as_secs_f64())).ceil() as u64;
+    let steps = (config.duration.as_secs_f64() / delta.as_secs_f64()).ceil() as u64;
+
+    let mut reachable = Grid::new(app.arena, config.cell_size);
+    let spawn = reachable.cell(app.player.pos_x, app.player.pos_y);
+    reachable.cells[spawn] = true;
+
+    let mut walls = Grid::new(app.arena, config.cell_size);
+    for i in 0..walls.cells.len() {
+        let (x, y) = walls.center(i);
+        walls.cells[i] = app.obstacles.iter().any(|o| o.overlaps(x, y, radius));
+    }
+
+    let mut bullets: Vec<Bullet> = vec![];
+    let mut danger = Grid::new(app.arena, config.cell_size);
+    let mut unavoidable = vec![];
+    let mut in_trouble = false;
+
+    let mut slice_steps = 0;
+    for step in 1..=steps {
+        app.enemy.tick(delta, &mut app.player, &mut app.rng).ok()?;
+        bullets.extend(app.enemy.bullets());
+        for b in &mut bullets {
+            b.pos_x += b.velocity_x * delta.as_secs_f64();
+            b.pos_y += b.velocity_y * delta.as_secs_f64();
+            app.arena.reflect(b);
+            if !app.arena.contains(b.pos_x, b.pos_y, Arena::CULL_MARGIN) {
+                b.will_remove = true;
+            }
+            for o in &mut app.obstacles {
+                o.block(b);
+            }
+            danger.stamp(b.pos_x, b.pos_y, radius);
+        }
+        bullets.retain(|b| !b.will_remove);
+
+        slice_steps += 1;
+        if slice_steps < slice {
+            continue;
+        }
+        slice_steps = 0;
+
+        let grown = reachable.dilate();
+        let safe: Vec<bool> = grown
+            .iter()
+            .zip(&danger.cells)
+            .zip(&walls.cells)
+            .map(|((&r, &d), &w)| r && !d && !w)
+            .collect();
+
+        if safe.iter().any(|&s| s) {
+            reachable.cells = safe;
+            in_trouble = false;
+        } else {
+            if !in_trouble {
+                unavoidable.push(flag(&reachable, seed, step as f64 * delta.as_secs_f64()));
+            }
+            in_trouble = true;
+            // got hit, but still somewhere around there
+            reachable.cells = grown
+                .iter()
+                .zip(&walls.cells)
+                .map(|(&r, &w)| r && !w)
+                .collect();
+        }
+        danger.cells.fill(false);
+    }
+    Some(unavoidable)
+}
+
+fn flag(reachable: &Grid, seed: u64, time: f64) -> Unavoidable {
+    let cells: Vec<_> = (0..reachable.cells.len())
+        .filter(|&i| reachable.cells[i])
+        .map(|i| reachable.center(i))
+        .collect();
+    let n = cells.len().max(1) as f64;
+    let mid_x = cells.iter().map(|c| c.0).sum::<f64>() / n;
+    let mid_y = cells.iter().map(|c| c.1).sum::<f64>() / n;
+    let (x, y) = cells
+        .iter()
+        .copied()
+        .min_by(|a, b| {
+            crate::dis(a.0, a.1, mid_x, mid_y).total_cmp(&crate::dis(b.0, b.1, mid_x, mid_y))
+        })
+        .unwrap_or((mid_x, mid_y));
+
+    Unavoidable {
+        seed,
+        time,
+        x,
+        y,
+        region: cells.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_stage_is_dodgeable() {
+        let config = DodgeConfig::default();
+        let reports: Vec<_> = (0..).map_while(|s| verify(s, &config)).collect();
+        assert!(!reports.is_empty());
+        for report in reports {
+            assert!(
+                report.is_dodgeable(),
+                "stage {} has unavoidable hits: {:?}",
+                report.stage,
+                report.unavoidable
+            );
+        }
+    }
+}
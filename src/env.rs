@@ -0,0 +1,348 @@
+use std::f64::consts::FRAC_1_SQRT_2;
+
+use crate::app::{App, AppResult, Bullet, GameEvent, GameResult};
+
+/// What [`Env`] observations look like
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObservationKind {
+    /// Player, enemy and the nearest `bullets` enemy bullets, see
+    /// [`Env::observe`] for the layout
+    Entities { bullets: usize },
+    /// The arena rasterized into a `width` × `height` grid per channel, see
+    /// [`Env::CHANNELS`]
+    Grid { width: usize, height: usize },
+}
+
+/// Which [`Action`]s an [`Env`] accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionSpace {
+    /// `Action::Discrete(i)` with `i` in `0..Env::DISCRETE_ACTIONS`
+    Discrete,
+    /// `Action::Continuous`
+    Continuous,
+}
+
+/// Input for one step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// `movement * 9 + shoot`, each one of [`Env::DIRECTIONS`] with 0 meaning
+    /// none
+    Discrete(usize),
+    /// Movement, clamped to unit length, and the direction to shoot in
+    Continuous {
+        move_x: f64,
+        move_y: f64,
+        shoot: Option<(f64, f64)>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub observation: ObservationKind,
+    pub action_space: ActionSpace,
+    /// Stage episodes start on
+    pub stage: usize,
+    /// Episodes are cut off after this many steps
+    pub max_steps: u64,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            observation: ObservationKind::Entities { bullets: 16 },
+            action_space: ActionSpace::Discrete,
+            stage: 0,
+            // five minutes of in-game time
+            max_steps: 30 * 60 * 5,
+        }
+    }
+}
+
+/// Reinforcement-learning style wrapper around the headless game.
+///
+/// Every [`Env::step`] advances the simulation one fixed step and returns
+/// the observation, reward and whether the episode is over. Rewards are
+/// damage dealt minus damage taken, plus [`Env::CLEAR_REWARD`] per stage
+/// cleared and minus [`Env::DEFEAT_PENALTY`] on defeat.
+#[derive(Debug, Clone)]
+pub struct Env {
+    pub config: EnvConfig,
+    app: App,
+}
+
+impl Env {
+    /// Unit directions for discrete actions, index 0 is none
+    pub const DIRECTIONS: [(f64, f64); 9] = [
+        (0., 0.),
+        (1., 0.),
+        (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        (0., 1.),
+        (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        (-1., 0.),
+        (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+        (0., -1.),
+        (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    ];
+    pub const DISCRETE_ACTIONS: usize = 9 * 9;
+    /// Grid channels: player, enemy, enemy bullets, player bullets, obstacles
+    pub const CHANNELS: usize = 5;
+    pub const CLEAR_REWARD: f64 = 100.;
+    pub const DEFEAT_PENALTY: f64 = 100.;
+
+    /// Fails if there's no `config.stage`
+    pub fn new(config: EnvConfig) -> AppResult<Self> {
+        let mut env = Self {
+            config,
+            app: App::new(),
+        };
+        env.reset(0)?;
+        Ok(env)
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    /// Start a new episode, returns its first observation
+    pub fn reset(&mut self, seed: u64) -> AppResult<Vec<f64>> {
+        let mut app = App::with_seed(seed);
+        if !app.load_stage(self.config.stage) {
+            return Err(format!("no stage {}", self.config.stage).into());
+        }
+        self.app = app;
+        Ok(self.observe())
+    }
+
+    /// Fails if `action` isn't one of the configured [`ActionSpace`]
+    pub fn step(&mut self, action: Action) -> AppResult<(Vec<f64>, f64, bool)> {
+        let events = Self::events(&self.app, self.config.action_space, action)?;
+        let app = &mut self.app;
+        let stage = app.stage_index;
+        let enemy_hp = app.enemy.hp();
+        let damage_taken = app.stats.damage_taken;
+        let clears = app.stats.stage_clears.len();
+
+        app.events = events;
+        app.step();
+
+        let mut reward = -((app.stats.damage_taken - damage_taken) as f64);
+        let cleared = app.stats.stage_clears.len() - clears;
+        if cleared > 0 || app.stage_index != stage {
+            reward += enemy_hp.max(0) as f64 + cleared as f64 * Self::CLEAR_REWARD;
+        } else {
+            reward += (enemy_hp - app.enemy.hp()).max(0) as f64;
+        }
+        if app.result == Some(GameResult::Defeated) {
+            reward -= Self::DEFEAT_PENALTY;
+        }
+
+        let done = app.result.is_some() || app.steps >= self.config.max_steps;
+        Ok((self.observe(), reward, done))
+    }
+
+    /// Length of the vectors returned by [`Env::observe`]
+    pub fn observation_len(&self) -> usize {
+        match self.config.observation {
+            ObservationKind::Entities { bullets } => 4 + 3 + bullets * 5,
+            ObservationKind::Grid { width, height } => Self::CHANNELS * width * height,
+        }
+    }
+
+    /// The world as a flat vector.
+    ///
+    /// Entities: `[x, y, hp, mp]` of the player, `[dx, dy, hp]` of the enemy,
+    /// then `[present, dx, dy, vx, vy]` for each of the nearest enemy bullets,
+    /// zero padded. Positions are scaled so the arena spans `[-1, 1]`, `d*`
+    /// are relative to the player, `hp`/`mp` are fractions of the maximum,
+    /// except for the enemy whose `hp` is raw.
+    ///
+    /// Grid: `1.` for every cell occupied in a channel, channel-major, rows
+    /// from the top of the arena.
+    pub fn observe(&self) -> Vec<f64> {
+        let app = &self.app;
+        let arena = &app.arena;
+        let mut obs = Vec::with_capacity(self.observation_len());
+
+        match self.config.observation {
+            ObservationKind::Entities { bullets } => {
+                let scale_x = (arena.max_x - arena.min_x) / 2.;
+                let scale_y = (arena.max_y - arena.min_y) / 2.;
+                let player = &app.player;
+                obs.extend([
+                    player.pos_x / scale_x,
+                    player.pos_y / scale_y,
+                    player.hp as f64 / player.max_hp as f64,
+                    player.mp as f64 / player.max_mp as f64,
+                ]);
+
+                let (x, y) = app.enemy.pos();
+                obs.extend([
+                    (x - player.pos_x) / scale_x,
+                    (y - player.pos_y) / scale_y,
+                    app.enemy.hp() as f64,
+                ]);
+
+                let mut nearest: Vec<_> = app.bullets.iter().filter(|b| !b.is_player).collect();
+                let distance =
+                    |b: &&Bullet| crate::dis(player.pos_x, player.pos_y, b.pos_x, b.pos_y);
+                nearest.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+                for i in 0..bullets {
+                    match nearest.get(i) {
+                        Some(b) => obs.extend([
+                            1.,
+                            (b.pos_x - player.pos_x) / scale_x,
+                            (b.pos_y - player.pos_y) / scale_y,
+                            b.velocity_x / scale_x,
+                            b.velocity_y / scale_y,
+                        ]),
+                        None => obs.extend([0.; 5]),
+                    }
+                }
+            }
+            ObservationKind::Grid { width, height } => {
+                obs.resize(Self::CHANNELS * width * height, 0.);
+                if obs.is_empty() {
+                    return obs;
+                }
+                let cell_w = (arena.max_x - arena.min_x) / width as f64;
+                let cell_h = (arena.max_y - arena.min_y) / height as f64;
+                let cell = |x: f64, y: f64| {
+                    if !arena.contains(x, y, 0.) {
+                        return None;
+                    }
+                    let col = (((x - arena.min_x) / cell_w) as usize).min(width - 1);
+                    let row = (((arena.max_y - y) / cell_h) as usize).min(height - 1);
+                    Some(row * width + col)
+                };
+                let mut mark = |channel: usize, x: f64, y: f64| {
+                    if let Some(i) = cell(x, y) {
+                        obs[channel * width * height + i] = 1.;
+                    }
+                };
+
+                mark(0, app.player.pos_x, app.player.pos_y);
+                let (x, y) = app.enemy.pos();
+                mark(1, x, y);
+                for b in &app.bullets {
+                    mark(if b.is_player { 3 } else { 2 }, b.pos_x, b.pos_y);
+                }
+                // a cell is blocked if the obstacle covers its center
+                for row in 0..height {
+                    for col in 0..width {
+                        let x = arena.min_x + (col as f64 + 0.5) * cell_w;
+                        let y = arena.max_y - (row as f64 + 0.5) * cell_h;
+                        if app.obstacles.iter().any(|o| o.overlaps(x, y, 0.)) {
+                            mark(4, x, y);
+                        }
+                    }
+                }
+            }
+        }
+        obs
+    }
+
+    /// Translate an action into game events
+    fn events(app: &App, space: ActionSpace, action: Action) -> AppResult<Vec<GameEvent>> {
+        let (move_x, move_y, shoot) = match (space, action) {
+            (ActionSpace::Discrete, Action::Discrete(i)) => {
+                if i >= Self::DISCRETE_ACTIONS {
+                    return Err(format!("no discrete action {i}").into());
+                }
+                let (move_x, move_y) = Self::DIRECTIONS[i / 9];
+                let shoot = Some(Self::DIRECTIONS[i % 9]).filter(|&d| d != (0., 0.));
+                (move_x, move_y, shoot)
+            }
+            (
+                ActionSpace::Continuous,
+                Action::Continuous {
+                    move_x,
+                    move_y,
+                    shoot,
+                },
+            ) => {
+                let len = move_x.hypot(move_y);
+                if len > 1. {
+                    (move_x / len, move_y / len, shoot)
+                } else {
+                    (move_x, move_y, shoot)
+                }
+            }
+            _ => return Err(format!("{action:?} is not in the {space:?} action space").into()),
+        };
+
+        let mut events = vec![];
+        if (move_x, move_y) != (0., 0.) {
+            events.push(GameEvent::PlayerMove(move_x, move_y));
+        }
+        if let Some((dx, dy)) = shoot {
+            if (dx, dy) != (0., 0.) {
+                // far out, so moving this step doesn't skew the direction
+                let reach = app.arena.max_x - app.arena.min_x;
+                let (x, y) = (app.player.pos_x, app.player.pos_y);
+                events.push(GameEvent::Shoot(x + dx * reach, y + dy * reach));
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(stage: usize) -> Env {
+        Env::new(EnvConfig {
+            stage,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn missing_stage_is_an_error() {
+        assert!(Env::new(EnvConfig {
+            stage: 99,
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn actions_must_match_the_space() {
+        let mut env = env(0);
+        assert!(env.step(Action::Discrete(Env::DISCRETE_ACTIONS)).is_err());
+        let continuous = Action::Continuous {
+            move_x: 1.,
+            move_y: 0.,
+            shoot: None,
+        };
+        assert!(env.step(continuous).is_err());
+        assert!(env.step(Action::Discrete(0)).is_ok());
+    }
+
+    #[test]
+    fn reset_is_deterministic() {
+        let mut env = env(1);
+        let run = |env: &mut Env| {
+            let mut observations = vec![env.reset(3).unwrap()];
+            for i in 0..300 {
+                let (obs, _, _) = env
+                    .step(Action::Discrete(i * 7 % Env::DISCRETE_ACTIONS))
+                    .unwrap();
+                observations.push(obs);
+            }
+            observations
+        };
+        let first = run(&mut env);
+        assert_eq!(first, run(&mut env));
+    }
+
+    #[test]
+    fn clearing_the_last_stage_is_done() {
+        let mut env = env(2);
+        env.app.enemy.take_damage(env.app.enemy.hp());
+        let (_, reward, done) = env.step(Action::Discrete(0)).unwrap();
+        assert!(done);
+        assert!(reward >= Env::CLEAR_REWARD);
+    }
+}
@@ -1,20 +1,24 @@
 use crate::app::AppResult;
-use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent};
+use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, MouseEvent};
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 
 /// Terminal events.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Event {
     /// Terminal tick.
     Tick(Duration),
-    /// Key press.
+    /// Key press, repeat or release.
     Key(KeyEvent),
     /// Mouse click/scroll.
     Mouse(MouseEvent),
     /// Terminal resize.
     Resize(u16, u16),
+    /// Terminal lost focus.
+    FocusLost,
+    /// A phrase was recognized from the microphone.
+    Speech(String),
 }
 
 /// Terminal event handler.
@@ -45,17 +49,11 @@ impl EventHandler {
 
                     if event::poll(timeout).expect("failed to poll new events") {
                         match event::read().expect("unable to read event") {
-                            CrosstermEvent::Key(e) => {
-                                if e.kind == KeyEventKind::Press {
-                                    sender.send(Event::Key(e))
-                                } else {
-                                    Ok(())
-                                }
-                            }
+                            CrosstermEvent::Key(e) => sender.send(Event::Key(e)),
                             CrosstermEvent::Mouse(e) => sender.send(Event::Mouse(e)),
                             CrosstermEvent::Resize(w, h) => sender.send(Event::Resize(w, h)),
                             CrosstermEvent::FocusGained => Ok(()),
-                            CrosstermEvent::FocusLost => Ok(()),
+                            CrosstermEvent::FocusLost => sender.send(Event::FocusLost),
                             CrosstermEvent::Paste(_) => unimplemented!(),
                         }
                         .expect("failed to send terminal event")
@@ -77,6 +75,11 @@ impl EventHandler {
         }
     }
 
+    /// A sender for events from other sources, like the microphone.
+    pub fn sender(&self) -> mpsc::Sender<Event> {
+        self.sender.clone()
+    }
+
     /// Receive the next event from the handler thread.
     ///
     /// This function will always block the current thread if
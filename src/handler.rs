@@ -1,42 +1,196 @@
+use std::time::{Duration, Instant};
+
 use crate::{
-    app::{App, AppResult, GameEvent},
-    skill::Skill,
+    app::{AimMode, App, AppResult, GameEvent, GameLog},
+    keymap::{Action, Key, RebindMenu},
+    skill::Language,
+    viewer::ReplayViewer,
 };
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
 /// Handles the key events and updates the state of [`App`].
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
-    match key_event.code {
-        // Exit application on `ESC` or `q`
-        KeyCode::Esc | KeyCode::Char('q') => {
-            app.quit();
-        }
-        // Exit application on `Ctrl-C`
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                app.quit();
+    app.keys.on_key_event(&key_event, Instant::now());
+    if key_event.kind != KeyEventKind::Press {
+        return Ok(());
+    }
+
+    // Exit application on `Ctrl-C`, whatever the bindings
+    if matches!(key_event.code, KeyCode::Char('c') | KeyCode::Char('C'))
+        && key_event.modifiers == KeyModifiers::CONTROL
+    {
+        app.quit();
+        return Ok(());
+    }
+
+    if app.rebind.is_some() {
+        return handle_rebind_key_events(key_event, app);
+    }
+
+    // `F1` always opens the key bindings, so no profile can lock them away
+    if key_event.code == KeyCode::F(1) {
+        app.rebind = Some(RebindMenu::default());
+        return Ok(());
+    }
+
+    match app.bindings.keymap().action(key_event.code) {
+        Some(Action::Quit) => app.quit(),
+        Some(Action::Pause) => app.paused = !app.paused,
+        // for testing
+        Some(Action::Cast) => app.events.push(GameEvent::Cast(Language::Python)),
+        Some(Action::Dodge) => app.events.push(GameEvent::Dash),
+        Some(Action::Bomb) => app.events.push(GameEvent::Bomb),
+        Some(Action::Rebind) => app.rebind = Some(RebindMenu::default()),
+        Some(Action::Fire) => {
+            let (x, y) = app.aim_target();
+            app.events.push(GameEvent::Shoot(x, y));
+        }
+        Some(Action::ZoomIn) => app.camera.zoom_in(),
+        Some(Action::ZoomOut) => app.camera.zoom_out(),
+        Some(Action::CycleAim) => {
+            // the cursor starts where the last mode was aiming
+            let (x, y) = app.aim_target();
+            app.cursor = app.arena.clamp(x, y, 0.);
+            app.aim = app.aim.next();
+            app.logs.push(GameLog(format!("aim: {}", app.aim.name())));
+        }
+        // movement, focus and the cursor follow held keys, see `handle_tick`
+        Some(
+            Action::Focus
+            | Action::MoveUp
+            | Action::MoveDown
+            | Action::MoveLeft
+            | Action::MoveRight
+            | Action::AimUp
+            | Action::AimDown
+            | Action::AimLeft
+            | Action::AimRight,
+        )
+        | None => {}
+    }
+    Ok(())
+}
+
+/// Handles the key events of the key bindings screen.
+///
+/// Its own keys aren't configurable, and `F1` opening it can't be bound away,
+/// so a broken profile can always be fixed.
+fn handle_rebind_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    let Some(menu) = &mut app.rebind else {
+        return Ok(());
+    };
+
+    if menu.waiting {
+        menu.waiting = false;
+        if key_event.code != KeyCode::Esc {
+            if Key::new(key_event.code).name().is_none() {
+                app.logs
+                    .push(GameLog("this key can't be bound".to_string()));
+                return Ok(());
             }
+            if key_event.code == KeyCode::F(1) && menu.action() != Action::Rebind {
+                app.logs
+                    .push(GameLog("F1 always opens key bindings".to_string()));
+                return Ok(());
+            }
+            let action = menu.action();
+            app.bindings.keymap_mut().bind(action, key_event.code);
+            save_bindings(app);
         }
-        // Move handlers
-        KeyCode::Right | KeyCode::Char('d') => {
-            app.events.push(GameEvent::PlayerMove(1., 0.));
+        return Ok(());
+    }
+
+    match key_event.code {
+        KeyCode::Esc | KeyCode::F(1) => app.rebind = None,
+        KeyCode::Up => menu.selected = menu.selected.saturating_sub(1),
+        KeyCode::Down => menu.selected = (menu.selected + 1).min(Action::ALL.len() - 1),
+        KeyCode::Enter => menu.waiting = true,
+        KeyCode::Backspace | KeyCode::Delete => {
+            let action = menu.action();
+            app.bindings.keymap_mut().unbind(action);
+            save_bindings(app);
         }
-        KeyCode::Left | KeyCode::Char('a') => {
-            app.events.push(GameEvent::PlayerMove(-1., 0.));
+        KeyCode::Tab => {
+            app.bindings.next_profile();
+            save_bindings(app);
         }
-        KeyCode::Up | KeyCode::Char('w') => {
-            app.events.push(GameEvent::PlayerMove(0., 1.));
+        _ => {}
+    }
+    Ok(())
+}
+
+fn save_bindings(app: &mut App) {
+    if let Err(e) = app.bindings.save() {
+        app.logs
+            .push(GameLog(format!("failed to save key bindings: {e}")));
+    }
+}
+
+/// Handles the tick event, moving the player and the aim cursor along the
+/// held keys before advancing the game.
+pub fn handle_tick(elapsed: Duration, app: &mut App) {
+    /// World units the cursor moves per second
+    const CURSOR_SPEED: f64 = 40.;
+
+    let now = Instant::now();
+    let keymap = app.bindings.keymap();
+    let direction = |right, left, up, down| {
+        let held = |action| keymap.is_held(action, &app.keys, now);
+        let x = held(right) as i8 - held(left) as i8;
+        let y = held(up) as i8 - held(down) as i8;
+        crate::norm(x as f64, y as f64)
+    };
+
+    app.movement = direction(
+        Action::MoveRight,
+        Action::MoveLeft,
+        Action::MoveUp,
+        Action::MoveDown,
+    );
+    app.focus = keymap.is_held(Action::Focus, &app.keys, now);
+    let (x, y) = direction(
+        Action::AimRight,
+        Action::AimLeft,
+        Action::AimUp,
+        Action::AimDown,
+    );
+    if app.aim == AimMode::Cursor && !app.paused {
+        let distance = CURSOR_SPEED * elapsed.as_secs_f64();
+        app.move_cursor(x * distance, y * distance);
+    }
+
+    app.update(elapsed);
+}
+
+/// Handles the key events of the replay viewer.
+pub fn handle_viewer_key_events(key_event: KeyEvent, viewer: &mut ReplayViewer) -> AppResult<()> {
+    if key_event.kind != KeyEventKind::Press {
+        return Ok(());
+    }
+
+    match key_event.code {
+        KeyCode::Esc | KeyCode::Char('q') => viewer.quit(),
+        KeyCode::Char('c') | KeyCode::Char('C') if key_event.modifiers == KeyModifiers::CONTROL => {
+            viewer.quit()
         }
-        KeyCode::Down | KeyCode::Char('s') => {
-            app.events.push(GameEvent::PlayerMove(0., -1.));
+        KeyCode::Char(' ') => viewer.toggle_pause(),
+        KeyCode::Right => viewer.seek_forward(),
+        KeyCode::Left => viewer.seek_backward(),
+        // frame step, pause first so it stays there
+        KeyCode::Char('.') => {
+            viewer.paused = true;
+            viewer.step_forward();
         }
-        // for testing
-        KeyCode::Char('p') => {
-            app.player.skills.push(Skill {
-                name: "Python".to_string(),
-            });
+        KeyCode::Char(',') => {
+            viewer.paused = true;
+            viewer.step_back();
         }
-        // Other handlers you could add here.
+        KeyCode::Char('+') | KeyCode::Char('=') => viewer.faster(),
+        KeyCode::Char('-') => viewer.slower(),
+        KeyCode::Char(']') => viewer.next_stage(),
+        KeyCode::Char('[') => viewer.prev_stage(),
+        KeyCode::Home => viewer.seek(0),
+        KeyCode::End => viewer.seek(viewer.length),
         _ => {}
     }
     Ok(())
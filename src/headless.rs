@@ -0,0 +1,174 @@
+use std::io::{BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::{App, AppResult, GameEvent, GameResult, GameStats};
+use crate::bot::Bot;
+use crate::observation::Observation;
+
+/// Outcome of a run, printed as JSON by the `headless` binary
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub seed: u64,
+    pub steps: u64,
+    /// In-game time, `steps` fixed steps
+    pub seconds: f64,
+    /// `None` if the run hit the step limit before it ended
+    pub result: Option<GameResult>,
+    /// Stage the run ended on
+    pub stage: usize,
+    pub hp: isize,
+    pub max_hp: isize,
+    pub mp: isize,
+    pub max_mp: isize,
+    pub stats: GameStats,
+}
+
+impl Summary {
+    pub fn new(app: &App) -> Self {
+        Self {
+            seed: app.seed,
+            steps: app.steps,
+            seconds: app.steps as f64 * App::FIXED_STEP.as_secs_f64(),
+            result: app.result,
+            stage: app.stage_index,
+            hp: app.player.hp,
+            max_hp: app.player.max_hp,
+            mp: app.player.mp,
+            max_mp: app.player.max_mp,
+            stats: app.stats.clone(),
+        }
+    }
+}
+
+/// Simulate as fast as possible without a terminal, until the run ends or
+/// `max_steps` steps were simulated.
+///
+/// Inputs come from `app.playback` if there's one, otherwise `app.events`
+/// are applied on the first step.
+pub fn run(mut app: App, max_steps: u64) -> Summary {
+    while app.result.is_none() && app.steps < max_steps {
+        app.step();
+    }
+    Summary::new(&app)
+}
+
+/// Like [`run`], but `bot` provides the inputs every step
+pub fn run_with_bot(mut app: App, bot: &mut dyn Bot, max_steps: u64) -> Summary {
+    while app.result.is_none() && app.steps < max_steps {
+        app.events = bot.act(&Observation::new(&app));
+        app.step();
+    }
+    Summary::new(&app)
+}
+
+/// A line read by [`serve`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    /// Start over, answered with the first observation
+    Reset {
+        #[serde(default)]
+        seed: u64,
+        #[serde(default)]
+        stage: usize,
+    },
+    /// Apply `events` and advance `steps` steps, answered with the
+    /// observation after them
+    Step {
+        #[serde(default)]
+        events: Vec<GameEvent>,
+        #[serde(default = "one")]
+        steps: u64,
+    },
+    /// Stop serving, answered with the [`Summary`] of the run
+    Quit,
+}
+
+fn one() -> u64 {
+    1
+}
+
+/// A line written by [`serve`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Response {
+    Observation {
+        observation: Observation,
+        result: Option<GameResult>,
+    },
+    Summary {
+        summary: Summary,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl Response {
+    fn observe(app: &App) -> Self {
+        Self::Observation {
+            observation: Observation::new(app),
+            result: app.result,
+        }
+    }
+}
+
+/// Drive the game in lockstep over newline-delimited JSON: every [`Request`]
+/// read from `input` is answered with exactly one [`Response`] on `output`.
+///
+/// ```text
+/// > {"cmd": "reset", "seed": 42}
+/// < {"observation": {...}, "result": null}
+/// > {"cmd": "step", "events": [{"PlayerMove": [1, 0]}, {"Shoot": [10, 5]}]}
+/// < {"observation": {...}, "result": null}
+/// > {"cmd": "quit"}
+/// < {"summary": {...}}
+/// ```
+///
+/// Bad requests are answered with `{"error": "..."}` and otherwise ignored.
+/// Returns when `input` ends or on `quit`.
+pub fn serve(mut app: App, input: impl BufRead, mut output: impl Write) -> AppResult<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str(&line) {
+            Ok(Request::Reset { seed, stage }) => {
+                let mut new = App::with_seed(seed);
+                if new.load_stage(stage) {
+                    app = new;
+                    Response::observe(&app)
+                } else {
+                    Response::Error {
+                        error: format!("no stage {stage}"),
+                    }
+                }
+            }
+            Ok(Request::Step { events, steps }) => {
+                app.events = events;
+                for _ in 0..steps {
+                    app.step();
+                }
+                Response::observe(&app)
+            }
+            Ok(Request::Quit) => {
+                let summary = Summary::new(&app);
+                serde_json::to_writer(&mut output, &Response::Summary { summary })?;
+                writeln!(output)?;
+                output.flush()?;
+                return Ok(());
+            }
+            Err(e) => Response::Error {
+                error: e.to_string(),
+            },
+        };
+
+        serde_json::to_writer(&mut output, &response)?;
+        writeln!(output)?;
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+
+/// Keys currently held down.
+///
+/// Terminals with the kitty keyboard protocol report key releases. Others
+/// only repeat presses at the OS key-repeat rate, so there a key counts as
+/// released when it hasn't been repeated for a while.
+#[derive(Debug, Clone, Default)]
+pub struct HeldKeys {
+    /// When each key was last pressed or repeated, and whether it repeated
+    keys: HashMap<KeyCode, (Instant, bool)>,
+    /// Set once a release event arrived, timeouts aren't needed anymore
+    releases: bool,
+}
+
+impl HeldKeys {
+    /// How long a key stays held after the first press without releases,
+    /// about the OS delay before a key starts repeating
+    const PRESS_TIMEOUT: Duration = Duration::from_millis(500);
+    /// How long a key stays held after a repeat without releases
+    const REPEAT_TIMEOUT: Duration = Duration::from_millis(100);
+
+    pub fn on_key_event(&mut self, evt: &KeyEvent, now: Instant) {
+        let code = match evt.code {
+            // shift could change between press and release
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        match evt.kind {
+            KeyEventKind::Press | KeyEventKind::Repeat => {
+                // a press long after the last one timed out is a new press
+                let repeated = evt.kind == KeyEventKind::Repeat || self.is_held(code, now);
+                self.keys.insert(code, (now, repeated));
+            }
+            KeyEventKind::Release => {
+                self.keys.remove(&code);
+                self.releases = true;
+            }
+        }
+    }
+
+    pub fn is_held(&self, code: KeyCode, now: Instant) -> bool {
+        let Some(&(last, repeated)) = self.keys.get(&code) else {
+            return false;
+        };
+        if self.releases {
+            return true;
+        }
+        let timeout = if repeated {
+            Self::REPEAT_TIMEOUT
+        } else {
+            Self::PRESS_TIMEOUT
+        };
+        now.duration_since(last) < timeout
+    }
+
+    /// Forget every key, when focus is lost releases may never arrive
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+}
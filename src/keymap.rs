@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crossterm::event::KeyCode;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::app::AppResult;
+use crate::input::HeldKeys;
+
+/// Things the player can do with a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    AimUp,
+    AimDown,
+    AimLeft,
+    AimRight,
+    Fire,
+    CycleAim,
+    ZoomIn,
+    ZoomOut,
+    Cast,
+    Dodge,
+    Focus,
+    Bomb,
+    Pause,
+    Rebind,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 19] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::AimUp,
+        Action::AimDown,
+        Action::AimLeft,
+        Action::AimRight,
+        Action::Fire,
+        Action::CycleAim,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Cast,
+        Action::Dodge,
+        Action::Focus,
+        Action::Bomb,
+        Action::Pause,
+        Action::Rebind,
+        Action::Quit,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::AimUp => "Aim up",
+            Action::AimDown => "Aim down",
+            Action::AimLeft => "Aim left",
+            Action::AimRight => "Aim right",
+            Action::Fire => "Fire",
+            Action::CycleAim => "Aim mode",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::Cast => "Cast",
+            Action::Dodge => "Dodge",
+            Action::Focus => "Focus",
+            Action::Bomb => "Bomb",
+            Action::Pause => "Pause",
+            Action::Rebind => "Key bindings",
+            Action::Quit => "Quit",
+        }
+    }
+}
+
+/// A bindable key, written in config files by its name, like `w`, `Up` or
+/// `Space`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key(pub KeyCode);
+
+impl Key {
+    /// Letters are bound regardless of shift
+    pub fn new(code: KeyCode) -> Self {
+        match code {
+            KeyCode::Char(c) => Self(KeyCode::Char(c.to_ascii_lowercase())),
+            code => Self(code),
+        }
+    }
+
+    /// `None` for keys that can't be bound
+    pub fn name(&self) -> Option<String> {
+        let name = match self.0 {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("F{n}"),
+            KeyCode::Up => "Up".to_string(),
+            KeyCode::Down => "Down".to_string(),
+            KeyCode::Left => "Left".to_string(),
+            KeyCode::Right => "Right".to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PageUp".to_string(),
+            KeyCode::PageDown => "PageDown".to_string(),
+            KeyCode::Insert => "Insert".to_string(),
+            KeyCode::Delete => "Delete".to_string(),
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let code = match name {
+            "Space" => KeyCode::Char(' '),
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            "Enter" => KeyCode::Enter,
+            "Esc" => KeyCode::Esc,
+            "Tab" => KeyCode::Tab,
+            "Backspace" => KeyCode::Backspace,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            "Insert" => KeyCode::Insert,
+            "Delete" => KeyCode::Delete,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    (Some('F'), Some(_)) => KeyCode::F(name[1..].parse().ok()?),
+                    _ => return None,
+                }
+            }
+        };
+        Some(Self::new(code))
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.name() {
+            Some(name) => serializer.serialize_str(&name),
+            None => Err(serde::ser::Error::custom(format!(
+                "unbindable key {:?}",
+                self.0
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Self::from_name(&name).ok_or_else(|| de::Error::custom(format!("unknown key `{name}`")))
+    }
+}
+
+/// Keys bound to each action
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Keymap(pub BTreeMap<Action, Vec<Key>>);
+
+impl Keymap {
+    fn new(bindings: &[(Action, &[&str])]) -> Self {
+        Self(
+            bindings
+                .iter()
+                .map(|(action, keys)| {
+                    let keys = keys.iter().filter_map(|k| Key::from_name(k)).collect();
+                    (*action, keys)
+                })
+                .collect(),
+        )
+    }
+
+    /// WASD and arrows
+    pub fn default_profile() -> Self {
+        Self::new(&[
+            (Action::MoveUp, &["w", "Up"]),
+            (Action::MoveDown, &["s", "Down"]),
+            (Action::MoveLeft, &["a", "Left"]),
+            (Action::MoveRight, &["d", "Right"]),
+            (Action::AimUp, &["i"]),
+            (Action::AimDown, &["k"]),
+            (Action::AimLeft, &["j"]),
+            (Action::AimRight, &["l"]),
+            (Action::Fire, &["f"]),
+            (Action::CycleAim, &["r"]),
+            (Action::ZoomIn, &["="]),
+            (Action::ZoomOut, &["-"]),
+            (Action::Cast, &["p"]),
+            (Action::Dodge, &["Space"]),
+            (Action::Focus, &["e"]),
+            (Action::Bomb, &["x"]),
+            (Action::Pause, &["Enter"]),
+            (Action::Rebind, &["F1"]),
+            (Action::Quit, &["q", "Esc"]),
+        ])
+    }
+
+    /// IJKL and arrows, for the mouse in the left hand
+    pub fn left_handed_profile() -> Self {
+        Self::new(&[
+            (Action::MoveUp, &["i", "Up"]),
+            (Action::MoveDown, &["k", "Down"]),
+            (Action::MoveLeft, &["j", "Left"]),
+            (Action::MoveRight, &["l", "Right"]),
+            (Action::AimUp, &["w"]),
+            (Action::AimDown, &["s"]),
+            (Action::AimLeft, &["a"]),
+            (Action::AimRight, &["d"]),
+            (Action::Fire, &["f"]),
+            (Action::CycleAim, &["r"]),
+            (Action::ZoomIn, &["="]),
+            (Action::ZoomOut, &["-"]),
+            (Action::Cast, &["p"]),
+            (Action::Dodge, &["Space"]),
+            (Action::Focus, &["e"]),
+            (Action::Bomb, &["x"]),
+            (Action::Pause, &["Enter"]),
+            (Action::Rebind, &["F1"]),
+            (Action::Quit, &["q", "Esc"]),
+        ])
+    }
+
+    /// HJKL and arrows
+    pub fn vim_profile() -> Self {
+        Self::new(&[
+            (Action::MoveUp, &["k", "Up"]),
+            (Action::MoveDown, &["j", "Down"]),
+            (Action::MoveLeft, &["h", "Left"]),
+            (Action::MoveRight, &["l", "Right"]),
+            (Action::AimUp, &["w"]),
+            (Action::AimDown, &["s"]),
+            (Action::AimLeft, &["a"]),
+            (Action::AimRight, &["d"]),
+            (Action::Fire, &["f"]),
+            (Action::CycleAim, &["r"]),
+            (Action::ZoomIn, &["="]),
+            (Action::ZoomOut, &["-"]),
+            (Action::Cast, &["p"]),
+            (Action::Dodge, &["Space"]),
+            (Action::Focus, &["e"]),
+            (Action::Bomb, &["x"]),
+            (Action::Pause, &["Enter"]),
+            (Action::Rebind, &["F1"]),
+            (Action::Quit, &["q", "Esc"]),
+        ])
+    }
+
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        let key = Key::new(code);
+        self.0
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.0.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    /// Add `code` to `action`, taking it away from any other action
+    pub fn bind(&mut self, action: Action, code: KeyCode) {
+        let key = Key::new(code);
+        for keys in self.0.values_mut() {
+            keys.retain(|k| *k != key);
+        }
+        self.0.entry(action).or_default().push(key);
+    }
+
+    pub fn unbind(&mut self, action: Action) {
+        self.0.insert(action, vec![]);
+    }
+
+    pub fn is_held(&self, action: Action, held: &HeldKeys, now: Instant) -> bool {
+        self.keys(action).iter().any(|k| held.is_held(k.0, now))
+    }
+}
+
+/// Every input profile and which one is in use, saved between runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyConfig {
+    pub profile: String,
+    pub profiles: BTreeMap<String, Keymap>,
+}
+
+impl Default for KeyConfig {
+    fn default() -> Self {
+        Self {
+            profile: "default".to_string(),
+            profiles: BTreeMap::from([
+                ("default".to_string(), Keymap::default_profile()),
+                ("left-handed".to_string(), Keymap::left_handed_profile()),
+                ("vim".to_string(), Keymap::vim_profile()),
+            ]),
+        }
+    }
+}
+
+impl KeyConfig {
+    /// `$XDG_CONFIG_HOME/normal_game_jam_2024/keys.json`, falling back to
+    /// `~/.config`
+    pub fn path() -> Option<PathBuf> {
+        let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(dir.join(env!("CARGO_PKG_NAME")).join("keys.json"))
+    }
+
+    /// Load the saved bindings, or the defaults if none were saved yet
+    pub fn load() -> AppResult<Self> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(Self::default());
+        };
+        let mut config: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        // profiles and actions that came out after the file was saved
+        for (name, defaults) in Self::default().profiles {
+            let keymap = config.profiles.entry(name).or_default();
+            for (action, keys) in defaults.0 {
+                if keymap.0.contains_key(&action) {
+                    continue;
+                }
+                let free = keys.into_iter().filter(|k| keymap.action(k.0).is_none());
+                keymap.0.insert(action, free.collect());
+            }
+        }
+        if !config.profiles.contains_key(&config.profile) {
+            config.profile = Self::default().profile;
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        let path = Self::path().ok_or("no config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.profiles[&self.profile]
+    }
+
+    pub fn keymap_mut(&mut self) -> &mut Keymap {
+        self.profiles
+            .get_mut(&self.profile)
+            .expect("active profile exists")
+    }
+
+    /// Switch to the profile after the active one
+    pub fn next_profile(&mut self) {
+        let mut names = self.profiles.keys().cycle();
+        names.by_ref().find(|name| **name == self.profile);
+        if let Some(name) = names.next() {
+            self.profile = name.clone();
+        }
+    }
+}
+
+/// State of the key bindings screen
+#[derive(Debug, Clone, Default)]
+pub struct RebindMenu {
+    /// Index into [`Action::ALL`]
+    pub selected: usize,
+    /// Waiting for the key to bind to the selected action
+    pub waiting: bool,
+}
+
+impl RebindMenu {
+    pub fn action(&self) -> Action {
+        Action::ALL[self.selected]
+    }
+}
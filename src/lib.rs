@@ -13,9 +13,51 @@ pub mod tui;
 /// Event handler.
 pub mod handler;
 
+/// Collision detection.
+pub mod collision;
+
+/// Seeded randomness.
+pub mod rng;
+
+/// Input recording and playback.
+pub mod replay;
+
+/// Replay viewer.
+pub mod viewer;
+
+/// Simulation without a terminal.
+pub mod headless;
+
+/// What outside players can see of the game.
+pub mod observation;
+
+/// Automated players.
+pub mod bot;
+
+/// Reinforcement-learning environment.
+pub mod env;
+
+/// Checks that enemy attack patterns can be dodged.
+pub mod dodge;
+
+/// Keyboard state tracking.
+pub mod input;
+
+/// Key bindings.
+pub mod keymap;
+
+/// World to screen mapping.
+pub mod viewport;
+
+/// Voice commands.
+pub mod speech_recog;
+
+mod arena;
 mod battle;
+mod camera;
+mod obstacle;
 mod skill;
-mod speech_recog;
+mod status;
 
 // generated binding
 mod gen {
@@ -61,10 +103,6 @@ pub(crate) fn rotate_vector(x: f64, y: f64, degrees: f64) -> (f64, f64) {
     (x_new, y_new)
 }
 
-pub(crate) fn map_range(min_s: f64, max_s: f64, min_t: f64, max_t: f64, t: f64) -> f64 {
-    let diff_s = max_s - min_s;
-    let diff_t = max_t - min_t;
-
-    let ratio = (t - min_t) / diff_t;
-    min_s + (diff_s * ratio)
+pub(crate) fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
 }
@@ -1,21 +1,103 @@
-use normal_game_jam_2024::app::{App, AppResult};
+use normal_game_jam_2024::app::{App, AppResult, GameLog};
 use normal_game_jam_2024::event::{Event, EventHandler};
-use normal_game_jam_2024::handler::handle_key_events;
+use normal_game_jam_2024::handler::{handle_key_events, handle_tick, handle_viewer_key_events};
+use normal_game_jam_2024::keymap::KeyConfig;
+use normal_game_jam_2024::replay::Replay;
+use normal_game_jam_2024::speech_recog;
 use normal_game_jam_2024::tui::Tui;
+use normal_game_jam_2024::viewer::ReplayViewer;
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-fn main() -> AppResult<()> {
-    // Create an application.
-    let mut app = App::new();
+/// Command line arguments
+#[derive(Debug, Default)]
+struct Args {
+    /// `--seed <n>`, a random seed is used if it's not given
+    seed: Option<u64>,
+    /// `--record <path>`, save the inputs of this run as a replay
+    record: Option<PathBuf>,
+    /// `--replay <path>`, watch a replay in the replay viewer
+    replay: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> AppResult<Self> {
+        let mut ret = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+            match arg.as_str() {
+                "--seed" => ret.seed = Some(value()?.parse()?),
+                "--record" => ret.record = Some(value()?.into()),
+                "--replay" => ret.replay = Some(value()?.into()),
+                _ => return Err(format!("unknown argument {arg}").into()),
+            }
+        }
+        Ok(ret)
+    }
+}
 
-    // Initialize the terminal user interface.
+/// Initialize the terminal user interface.
+fn init_tui() -> AppResult<Tui<CrosstermBackend<io::Stderr>>> {
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend)?;
     let events = EventHandler::new(33);
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
+    Ok(tui)
+}
+
+fn run_viewer(mut viewer: ReplayViewer) -> AppResult<()> {
+    let mut tui = init_tui()?;
+    let size = tui.size()?;
+    viewer.resize(size.width, size.height);
+
+    while viewer.running {
+        tui.draw_viewer(&mut viewer)?;
+        match tui.events.next()? {
+            Event::Tick(delta) => viewer.update(delta),
+            Event::Key(key_event) => handle_viewer_key_events(key_event, &mut viewer)?,
+            Event::Mouse(mouse_event) => viewer.on_mouse_event(mouse_event)?,
+            Event::Resize(width, height) => viewer.resize(width, height),
+            Event::FocusLost | Event::Speech(_) => {}
+        }
+    }
+
+    tui.exit()?;
+    Ok(())
+}
+
+fn main() -> AppResult<()> {
+    let args = Args::parse()?;
+
+    if let Some(path) = &args.replay {
+        return run_viewer(ReplayViewer::new(Replay::load(path)?));
+    }
+
+    // Create an application.
+    let seed = match args.seed {
+        Some(seed) => seed,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+    };
+    let mut app = App::with_seed(seed);
+    match KeyConfig::load() {
+        Ok(bindings) => app.bindings = bindings,
+        Err(e) => app
+            .logs
+            .push(GameLog(format!("failed to load key bindings: {e}"))),
+    }
+    if args.record.is_some() {
+        app.recording = Some(Replay::new(app.seed));
+    }
+
+    let mut tui = init_tui()?;
+    if let Err(e) = speech_recog::listen(tui.events.sender()) {
+        app.logs
+            .push(GameLog(format!("voice commands disabled: {e}")));
+    }
 
     // Start the main loop.
     while app.running {
@@ -23,14 +105,20 @@ fn main() -> AppResult<()> {
         tui.draw(&mut app)?;
         // Handle events.
         match tui.events.next()? {
-            Event::Tick(delta) => app.tick(delta),
+            Event::Tick(delta) => handle_tick(delta, &mut app),
             Event::Key(key_event) => handle_key_events(key_event, &mut app)?,
             Event::Mouse(mouse_event) => app.on_mouse_event(mouse_event)?,
             Event::Resize(_, _) => {}
+            Event::FocusLost => app.keys.clear(),
+            Event::Speech(text) => app.on_speech(&text),
         }
     }
 
     // Exit the user interface.
     tui.exit()?;
+
+    if let (Some(path), Some(recording)) = (&args.record, &app.recording) {
+        recording.save(path)?;
+    }
     Ok(())
 }
@@ -0,0 +1,78 @@
+use serde::Serialize;
+
+use crate::app::App;
+use crate::arena::Arena;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerState {
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub hp: isize,
+    pub mp: isize,
+    pub move_velocity: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnemyState {
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub hp: isize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulletState {
+    pub x: f64,
+    pub y: f64,
+    pub velocity_x: f64,
+    pub velocity_y: f64,
+    pub is_player: bool,
+}
+
+/// What a bot (or any other outside player) can see of the world
+#[derive(Debug, Clone, Serialize)]
+pub struct Observation {
+    pub step: u64,
+    pub stage: usize,
+    pub player: PlayerState,
+    pub enemies: Vec<EnemyState>,
+    pub bullets: Vec<BulletState>,
+    pub arena: Arena,
+}
+
+impl Observation {
+    pub fn new(app: &App) -> Self {
+        let (enemy_x, enemy_y) = app.enemy.pos();
+        Self {
+            step: app.steps,
+            stage: app.stage_index,
+            player: PlayerState {
+                x: app.player.pos_x,
+                y: app.player.pos_y,
+                radius: app.player.hit_radius(),
+                hp: app.player.hp,
+                mp: app.player.mp,
+                move_velocity: app.player.move_velocity,
+            },
+            enemies: vec![EnemyState {
+                x: enemy_x,
+                y: enemy_y,
+                radius: app.enemy.radius(),
+                hp: app.enemy.hp(),
+            }],
+            bullets: app
+                .bullets
+                .iter()
+                .map(|b| BulletState {
+                    x: b.pos_x,
+                    y: b.pos_y,
+                    velocity_x: b.velocity_x,
+                    velocity_y: b.velocity_y,
+                    is_player: b.is_player,
+                })
+                .collect(),
+            arena: app.arena,
+        }
+    }
+}
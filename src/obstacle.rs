@@ -0,0 +1,251 @@
+use ratatui::{
+    style::Color,
+    widgets::canvas::{self, Circle, Rectangle, Shape},
+};
+use std::time::Duration;
+
+use crate::app::Bullet;
+use crate::collision::Aabb;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ObstacleShape {
+    Circle {
+        x: f64,
+        y: f64,
+        radius: f64,
+    },
+    /// (x, y) is the bottom-left corner, same as [`Rectangle`]
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleKind {
+    /// Blocks movement and absorbs bullets
+    Wall,
+    /// Blocks movement and reflects bullets
+    Mirror,
+    /// Can be walked through, but damages whoever stands inside
+    Hazard { damage: isize },
+}
+
+#[derive(Debug, Clone)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+    pub kind: ObstacleKind,
+    /// Destructible obstacles have HP, `None` means indestructible
+    pub hp: Option<isize>,
+    timer: Duration,
+}
+
+impl Obstacle {
+    /// How often a hazard zone deals its damage
+    const HAZARD_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(shape: ObstacleShape, kind: ObstacleKind) -> Self {
+        Self {
+            shape,
+            kind,
+            hp: None,
+            timer: Duration::ZERO,
+        }
+    }
+
+    pub fn destructible(mut self, hp: isize) -> Self {
+        self.hp = Some(hp);
+        self
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.hp.is_some_and(|hp| hp <= 0)
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        match self.shape {
+            ObstacleShape::Circle { x, y, radius } => Aabb::from_circle(x, y, radius),
+            ObstacleShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => Aabb {
+                min_x: x,
+                min_y: y,
+                max_x: x + width,
+                max_y: y + height,
+            },
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        !matches!(self.kind, ObstacleKind::Hazard { .. })
+    }
+
+    /// Closest point on the obstacle's outline (or inside it) to (x, y)
+    fn closest_point(&self, x: f64, y: f64) -> (f64, f64) {
+        match self.shape {
+            ObstacleShape::Circle {
+                x: cx,
+                y: cy,
+                radius,
+            } => {
+                if crate::dis(x, y, cx, cy) <= radius {
+                    return (x, y);
+                }
+                let (nx, ny) = crate::norm(x - cx, y - cy);
+                (cx + nx * radius, cy + ny * radius)
+            }
+            ObstacleShape::Rect {
+                x: rx,
+                y: ry,
+                width,
+                height,
+            } => (x.clamp(rx, rx + width), y.clamp(ry, ry + height)),
+        }
+    }
+
+    /// Whether a circle at (x, y) overlaps the obstacle
+    pub fn overlaps(&self, x: f64, y: f64, radius: f64) -> bool {
+        let (px, py) = self.closest_point(x, y);
+        crate::dis(x, y, px, py) <= radius
+    }
+
+    /// Outward surface normal of the obstacle nearest to (x, y)
+    fn normal(&self, x: f64, y: f64) -> (f64, f64) {
+        match self.shape {
+            ObstacleShape::Circle { x: cx, y: cy, .. } => crate::norm(x - cx, y - cy),
+            ObstacleShape::Rect {
+                x: rx,
+                y: ry,
+                width,
+                height,
+            } => {
+                // pick the side with the smallest penetration
+                let sides = [
+                    (x - rx, (-1., 0.)),
+                    (rx + width - x, (1., 0.)),
+                    (y - ry, (0., -1.)),
+                    (ry + height - y, (0., 1.)),
+                ];
+                sides
+                    .into_iter()
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .map(|(_, n)| n)
+                    .unwrap()
+            }
+        }
+    }
+
+    /// Move a circle at (x, y) out of the obstacle if it's solid
+    pub fn push_out(&self, x: f64, y: f64, radius: f64) -> (f64, f64) {
+        if !self.is_solid() || !self.overlaps(x, y, radius) {
+            return (x, y);
+        }
+
+        let (px, py) = self.closest_point(x, y);
+        if crate::dis(x, y, px, py) > 0. {
+            // center is outside, push along the contact direction
+            let (nx, ny) = crate::norm(x - px, y - py);
+            return (px + nx * radius, py + ny * radius);
+        }
+
+        // center is inside, push out of the nearest side
+        let (nx, ny) = self.normal(x, y);
+        match self.shape {
+            ObstacleShape::Circle {
+                x: cx,
+                y: cy,
+                radius: r,
+            } => (cx + nx * (r + radius), cy + ny * (r + radius)),
+            ObstacleShape::Rect {
+                x: rx,
+                y: ry,
+                width,
+                height,
+            } => match (nx as i32, ny as i32) {
+                (-1, _) => (rx - radius, y),
+                (1, _) => (rx + width + radius, y),
+                (_, -1) => (x, ry - radius),
+                _ => (x, ry + height + radius),
+            },
+        }
+    }
+
+    /// Let a bullet inside this obstacle get absorbed or reflected
+    pub fn block(&mut self, bullet: &mut Bullet) {
+        if !self.is_solid() || !self.overlaps(bullet.pos_x, bullet.pos_y, 0.) {
+            return;
+        }
+
+        match self.kind {
+            ObstacleKind::Mirror => {
+                let (nx, ny) = self.normal(bullet.pos_x, bullet.pos_y);
+                let dot = bullet.velocity_x * nx + bullet.velocity_y * ny;
+                if dot >= 0. {
+                    // already reflected, on its way out
+                    return;
+                }
+                bullet.velocity_x -= 2. * dot * nx;
+                bullet.velocity_y -= 2. * dot * ny;
+            }
+            _ => bullet.will_remove = true,
+        }
+
+        if let Some(hp) = &mut self.hp {
+            *hp -= bullet.damage.max(1);
+        }
+    }
+
+    /// Advance the hazard timer, returns the damage dealt to everyone inside
+    pub fn tick(&mut self, delta: Duration) -> isize {
+        let ObstacleKind::Hazard { damage } = self.kind else {
+            return 0;
+        };
+
+        self.timer += delta;
+        let mut total = 0;
+        while self.timer >= Self::HAZARD_INTERVAL {
+            self.timer -= Self::HAZARD_INTERVAL;
+            total += damage;
+        }
+        total
+    }
+}
+
+impl Shape for Obstacle {
+    fn draw(&self, painter: &mut canvas::Painter) {
+        let color = match self.kind {
+            ObstacleKind::Wall if self.hp.is_some() => Color::LightGreen,
+            ObstacleKind::Wall => Color::Gray,
+            ObstacleKind::Mirror => Color::LightBlue,
+            ObstacleKind::Hazard { .. } => Color::LightMagenta,
+        };
+
+        match self.shape {
+            ObstacleShape::Circle { x, y, radius } => Circle {
+                x,
+                y,
+                radius,
+                color,
+            }
+            .draw(painter),
+            ObstacleShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => Rectangle {
+                x,
+                y,
+                width,
+                height,
+                color,
+            }
+            .draw(painter),
+        }
+    }
+}
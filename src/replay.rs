@@ -0,0 +1,254 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::app::{AppResult, GameEvent};
+use crate::skill::Language;
+
+/// Recorded inputs of a run.
+///
+/// Together with the seed, feeding the events back at the same simulation
+/// steps reproduces the run exactly. Saved as plain text, one event per line:
+///
+/// ```text
+/// ngj-replay 1
+/// version 0.1.0
+/// seed 42
+/// length 1200
+/// 15 m 1 0
+/// 18 h
+/// 20 s 12.5 -3
+/// 31 c Python
+/// ```
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// Game version the replay was recorded with
+    pub version: String,
+    pub seed: u64,
+    /// Steps simulated when recording stopped
+    pub length: u64,
+    /// Events with the step they were applied on, in order
+    pub events: Vec<(u64, GameEvent)>,
+    /// Next event to play back
+    cursor: usize,
+}
+
+impl Replay {
+    const MAGIC: &'static str = "ngj-replay 1";
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed,
+            length: 0,
+            events: vec![],
+            cursor: 0,
+        }
+    }
+
+    pub fn record(&mut self, step: u64, event: GameEvent) {
+        self.events.push((step, event));
+    }
+
+    /// Take the events applied on `step`, steps must be visited in order
+    pub fn events_at(&mut self, step: u64) -> Vec<GameEvent> {
+        // skip anything left behind
+        while self.events.get(self.cursor).is_some_and(|(s, _)| *s < step) {
+            self.cursor += 1;
+        }
+
+        let mut events = vec![];
+        while let Some((s, e)) = self.events.get(self.cursor) {
+            if *s != step {
+                break;
+            }
+            events.push(*e);
+            self.cursor += 1;
+        }
+        events
+    }
+
+    /// Rewind (or fast-forward) playback so the next events are those of `step`
+    pub fn seek(&mut self, step: u64) {
+        self.cursor = self.events.partition_point(|(s, _)| *s < step);
+    }
+
+    /// Whether all recorded steps have been played back
+    pub fn is_finished(&self, step: u64) -> bool {
+        step >= self.length
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.version == env!("CARGO_PKG_VERSION")
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> AppResult<()> {
+        let mut out = String::new();
+        writeln!(out, "{}", Self::MAGIC)?;
+        writeln!(out, "version {}", self.version)?;
+        writeln!(out, "seed {}", self.seed)?;
+        writeln!(out, "length {}", self.length)?;
+        for (step, evt) in &self.events {
+            // `{}` on f64 prints the shortest text parsing back to the same bits
+            match evt {
+                GameEvent::PlayerMove(x, y) => writeln!(out, "{step} m {x} {y}")?,
+                GameEvent::Shoot(x, y) => writeln!(out, "{step} s {x} {y}")?,
+                GameEvent::Cast(language) => writeln!(out, "{step} c {}", language.name())?,
+                GameEvent::Charge => writeln!(out, "{step} h")?,
+                GameEvent::CancelCharge => writeln!(out, "{step} n")?,
+                GameEvent::ShootSecondary(x, y) => writeln!(out, "{step} r {x} {y}")?,
+                GameEvent::Special(x, y) => writeln!(out, "{step} x {x} {y}")?,
+                GameEvent::CycleLoadout(direction) => writeln!(out, "{step} l {direction}")?,
+                GameEvent::Dash => writeln!(out, "{step} d")?,
+                GameEvent::Focus => writeln!(out, "{step} f")?,
+                GameEvent::Bomb => writeln!(out, "{step} b")?,
+            }
+        }
+        fs::write(path, out)?;
+        Ok(())
+    }
+
+    /// Load a script of inputs, a replay file without the header
+    pub fn load_script(path: impl AsRef<Path>, seed: u64) -> AppResult<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut replay = Self::new(seed);
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let evt =
+                parse_event(line).ok_or_else(|| format!("bad script event at line {}", i + 1))?;
+            replay.events.push(evt);
+        }
+        replay.events.sort_by_key(|(step, _)| *step);
+        replay.length = replay.events.last().map_or(0, |(step, _)| step + 1);
+        Ok(replay)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> AppResult<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines().enumerate();
+
+        if lines.next().map(|(_, l)| l) != Some(Self::MAGIC) {
+            return Err("not a replay file".into());
+        }
+
+        let mut header = |key: &str| -> AppResult<String> {
+            let (_, line) = lines.next().ok_or("unexpected end of replay header")?;
+            let value = line
+                .strip_prefix(key)
+                .and_then(|v| v.strip_prefix(' '))
+                .ok_or_else(|| format!("expected `{key}` in replay header"))?;
+            Ok(value.to_string())
+        };
+        let version = header("version")?;
+        let seed = header("seed")?.parse()?;
+        let length = header("length")?.parse()?;
+
+        let mut events = vec![];
+        for (i, line) in lines {
+            let evt =
+                parse_event(line).ok_or_else(|| format!("bad replay event at line {}", i + 1))?;
+            events.push(evt);
+        }
+
+        Ok(Self {
+            version,
+            seed,
+            length,
+            events,
+            cursor: 0,
+        })
+    }
+}
+
+fn parse_event(line: &str) -> Option<(u64, GameEvent)> {
+    let mut parts = line.split(' ');
+    let step = parts.next()?.parse().ok()?;
+    let evt = match parts.next()? {
+        "m" => GameEvent::PlayerMove(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?),
+        "s" => GameEvent::Shoot(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?),
+        "c" => GameEvent::Cast(Language::from_name(parts.next()?)?),
+        "h" => GameEvent::Charge,
+        "n" => GameEvent::CancelCharge,
+        "r" => GameEvent::ShootSecondary(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?),
+        "x" => GameEvent::Special(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?),
+        "l" => GameEvent::CycleLoadout(parts.next()?.parse().ok()?),
+        "d" => GameEvent::Dash,
+        "f" => GameEvent::Focus,
+        "b" => GameEvent::Bomb,
+        _ => return None,
+    };
+    Some((step, evt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::observation::Observation;
+
+    /// One of every event, with floats that don't print back exactly with a
+    /// fixed number of digits
+    fn replay() -> Replay {
+        let mut replay = Replay::new(42);
+        let events = [
+            GameEvent::PlayerMove(0.1 + 0.2, -1. / 3.),
+            GameEvent::Shoot(1e-300, -12.5),
+            GameEvent::Cast(Language::Cpp),
+            GameEvent::Charge,
+            GameEvent::CancelCharge,
+            GameEvent::ShootSecondary(f64::MAX, 2f64.sqrt()),
+            GameEvent::Special(-0., 30.000000000000004),
+            GameEvent::CycleLoadout(-1),
+            GameEvent::Dash,
+            GameEvent::Focus,
+            GameEvent::Bomb,
+        ];
+        for (i, evt) in events.into_iter().enumerate() {
+            replay.record(10 * i as u64, evt);
+            replay.record(10 * i as u64, GameEvent::PlayerMove(1., 0.));
+        }
+        replay.length = 300;
+        replay
+    }
+
+    fn round_trip(replay: &Replay) -> Replay {
+        let path = std::env::temp_dir().join(format!("ngj-replay-test-{}", std::process::id()));
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        loaded.unwrap()
+    }
+
+    fn play(replay: Replay) -> String {
+        let length = replay.length;
+        let mut app = App::from_replay(replay);
+        for _ in 0..length {
+            app.step();
+        }
+        serde_json::to_string(&(Observation::new(&app), &app.stats)).unwrap()
+    }
+
+    #[test]
+    fn saved_events_load_back() {
+        let replay = replay();
+        let loaded = round_trip(&replay);
+        assert_eq!(loaded.version, replay.version);
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.length, replay.length);
+        // compare the bits, `PartialEq` would let -0 pass for 0
+        assert_eq!(
+            format!("{:?}", loaded.events),
+            format!("{:?}", replay.events)
+        );
+    }
+
+    #[test]
+    fn loaded_replay_plays_the_same() {
+        let replay = replay();
+        let loaded = round_trip(&replay);
+        assert_eq!(play(loaded), play(replay));
+    }
+}
@@ -1,4 +1,76 @@
-#[derive(Debug)]
+use crate::status::{StatusEffect, StatusKind};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Programming languages a spell can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    C,
+    #[serde(rename = "C++")]
+    Cpp,
+    Go,
+    Python,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [Language::C, Language::Cpp, Language::Go, Language::Python];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::C => "C",
+            Language::Cpp => "C++",
+            Language::Go => "Go",
+            Language::Python => "Python",
+        }
+    }
+
+    /// Status effect applied on enemies hit by spells written in this language
+    pub fn effect(&self) -> Option<StatusEffect> {
+        match self {
+            Language::C => Some(StatusEffect::new(StatusKind::Burn, Duration::from_secs(2))),
+            Language::Cpp => Some(StatusEffect::new(
+                StatusKind::Freeze,
+                Duration::from_millis(500),
+            )),
+            Language::Go => None,
+            Language::Python => Some(StatusEffect::new(StatusKind::Slow, Duration::from_secs(2))),
+        }
+    }
+
+    /// Status effect the caster gets when casting a spell in this language
+    pub fn buff(&self) -> Option<StatusEffect> {
+        match self {
+            Language::Go => Some(StatusEffect::new(StatusKind::Haste, Duration::from_secs(3))),
+            Language::Cpp => Some(StatusEffect::stacked(
+                StatusKind::Shield,
+                Duration::from_secs(5),
+                2,
+            )),
+            Language::C | Language::Python => None,
+        }
+    }
+
+    /// Whether bullets of this language bounce off the arena walls
+    pub fn ricochet(&self) -> bool {
+        matches!(self, Language::Go)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Skill {
     pub name: String,
+    pub language: Language,
+}
+
+impl Skill {
+    pub fn new(language: Language) -> Self {
+        Self {
+            name: language.name().to_string(),
+            language,
+        }
+    }
 }
@@ -1,63 +1,72 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Sample, StreamConfig};
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, Sender};
 use std::time::Duration;
 use vosk::{DecodingState, Model, Recognizer};
 
-fn record_speech() {
+use crate::app::AppResult;
+use crate::event::Event;
+
+const MODEL_PATH: &str = "./vosk-model-small-en-us-0.15";
+
+/// Listen to the default microphone, sending every phrase recognized as an
+/// [`Event::Speech`].
+///
+/// Fails if there's no microphone or speech model, the game is then played
+/// without voice commands.
+pub fn listen(events: Sender<Event>) -> AppResult<()> {
     let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .expect("failed to get default input device");
-    let config = device
-        .default_input_config()
-        .expect("Failed to get default input config")
-        .config();
-
-    let model_path = "./vosk-model-small-en-us-0.15";
-    let model = Model::new(model_path).unwrap();
-    let mut recognizer = Recognizer::new(&model, config.sample_rate.0 as f32).unwrap();
+    let device = host.default_input_device().ok_or("no input device")?;
+    let config = device.default_input_config()?.config();
+
+    let model = Model::new(MODEL_PATH).ok_or(format!("no speech model at {MODEL_PATH}"))?;
+    let mut recognizer = Recognizer::new(&model, config.sample_rate.0 as f32)
+        .ok_or("failed to create the speech recognizer")?;
     // recognizer.set_max_alternatives(10);
     // recognizer.set_words(true);
     // recognizer.set_partial_words(true);
 
     let (sender, receiver) = channel();
 
-    let read_audio_handle = {
-        let sender = sender.clone();
-        std::thread::spawn(move || {
-            let input_stream = device
-                .build_input_stream(
-                    &config,
-                    move |data: &[i16], _info| {
-                        let data = data.to_vec();
-                        sender.send(data).expect("failed to send audio data");
-                    },
-                    |err| {
-                        eprintln!("audio input err: {err}");
-                    },
-                    None,
-                )
-                .expect("failed to build input stream");
-            input_stream.play().expect("failed to play input");
-
-            loop {
-                std::thread::sleep(Duration::from_secs(30));
+    // streams can't move between threads on some platforms, so it's built on
+    // the thread keeping it alive
+    std::thread::spawn(move || {
+        let Ok(input_stream) = device.build_input_stream(
+            &config,
+            move |data: &[i16], _info| {
+                // stops once recognition is gone
+                let _ = sender.send(data.to_vec());
+            },
+            // nowhere to report to without messing up the terminal
+            |_err| {},
+            None,
+        ) else {
+            return;
+        };
+        if input_stream.play().is_err() {
+            return;
+        }
+
+        loop {
+            std::thread::sleep(Duration::from_secs(30));
+        }
+    });
+
+    std::thread::spawn(move || {
+        while let Ok(sample) = receiver.recv() {
+            if matches!(
+                recognizer.accept_waveform(&sample),
+                DecodingState::Finalized
+            ) {
+                let result = recognizer.final_result();
+                let Some(text) = result.single().map(|r| r.text.to_string()) else {
+                    continue;
+                };
+                if !text.is_empty() && events.send(Event::Speech(text)).is_err() {
+                    return;
+                }
             }
-        })
-    };
-
-    let speech_to_text_handle = std::thread::spawn(move || loop {
-        let sample = receiver.recv().expect("failed to recv audio data");
-        if matches!(
-            recognizer.accept_waveform(&sample),
-            DecodingState::Finalized
-        ) {
-            let result = recognizer.final_result();
-            println!("{result:?}");
         }
     });
 
-    read_audio_handle.join().unwrap();
-    speech_to_text_handle.join().unwrap();
+    Ok(())
 }
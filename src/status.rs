@@ -0,0 +1,172 @@
+use ratatui::style::Color;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    /// Move (or act) at half speed
+    Slow,
+    /// Damage over time, one damage per stack every `BURN_INTERVAL`
+    Burn,
+    /// Cannot move or act at all
+    Freeze,
+    /// Cannot cast spells
+    Silence,
+    /// Move (or act) faster
+    Haste,
+    /// Absorbs one damage per stack
+    Shield,
+}
+
+impl StatusKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Slow => "Slow",
+            StatusKind::Burn => "Burn",
+            StatusKind::Freeze => "Freeze",
+            StatusKind::Silence => "Silence",
+            StatusKind::Haste => "Haste",
+            StatusKind::Shield => "Shield",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            StatusKind::Slow => Color::Blue,
+            StatusKind::Burn => Color::LightRed,
+            StatusKind::Freeze => Color::Cyan,
+            StatusKind::Silence => Color::Magenta,
+            StatusKind::Haste => Color::LightYellow,
+            StatusKind::Shield => Color::Green,
+        }
+    }
+
+    /// Max stacks, kinds with 1 stack only refresh their duration
+    fn max_stacks(&self) -> u32 {
+        match self {
+            StatusKind::Burn => 5,
+            StatusKind::Shield => 10,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub remaining: Duration,
+    pub stacks: u32,
+    timer: Duration,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, duration: Duration) -> Self {
+        Self::stacked(kind, duration, 1)
+    }
+
+    pub fn stacked(kind: StatusKind, duration: Duration, stacks: u32) -> Self {
+        Self {
+            kind,
+            remaining: duration,
+            stacks: stacks.min(kind.max_stacks()),
+            timer: Duration::ZERO,
+        }
+    }
+}
+
+/// Active status effects of a player or an enemy
+#[derive(Debug, Default, Clone)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    const BURN_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Apply a new effect, stackable kinds add up their stacks and
+    /// every kind refreshes to the longer duration.
+    pub fn apply(&mut self, effect: StatusEffect) {
+        match self.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            Some(e) => {
+                e.stacks = (e.stacks + effect.stacks).min(e.kind.max_stacks());
+                e.remaining = e.remaining.max(effect.remaining);
+            }
+            None => self.effects.push(effect),
+        }
+    }
+
+    /// Advance all effects, returns the damage dealt by damage-over-time effects
+    pub fn tick(&mut self, delta: Duration) -> isize {
+        let mut damage = 0;
+
+        for e in &mut self.effects {
+            // never tick longer than the effect lasts
+            let elapsed = delta.min(e.remaining);
+            e.remaining -= elapsed;
+
+            if e.kind == StatusKind::Burn {
+                e.timer += elapsed;
+                while e.timer >= Self::BURN_INTERVAL {
+                    e.timer -= Self::BURN_INTERVAL;
+                    damage += e.stacks as isize;
+                }
+            }
+        }
+
+        self.effects
+            .retain(|e| !e.remaining.is_zero() && e.stacks > 0);
+
+        damage
+    }
+
+    /// Consume shield stacks, returns the damage left after absorbing
+    pub fn absorb(&mut self, damage: isize) -> isize {
+        let Some(shield) = self
+            .effects
+            .iter_mut()
+            .find(|e| e.kind == StatusKind::Shield)
+        else {
+            return damage;
+        };
+
+        let absorbed = damage.clamp(0, shield.stacks as isize);
+        shield.stacks -= absorbed as u32;
+        if shield.stacks == 0 {
+            self.effects.retain(|e| e.kind != StatusKind::Shield);
+        }
+
+        damage - absorbed
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
+
+    /// Multiplier applied on movement and action timers
+    pub fn speed_multiplier(&self) -> f64 {
+        if self.has(StatusKind::Freeze) {
+            return 0.;
+        }
+
+        let mut m = 1.;
+        if self.has(StatusKind::Slow) {
+            m *= 0.5;
+        }
+        if self.has(StatusKind::Haste) {
+            m *= 1.5;
+        }
+        m
+    }
+
+    pub fn can_cast(&self) -> bool {
+        !self.has(StatusKind::Silence) && !self.has(StatusKind::Freeze)
+    }
+
+    /// Color of the most recently applied effect, used to tint shapes
+    pub fn tint(&self) -> Option<Color> {
+        self.effects.last().map(|e| e.kind.color())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.effects.iter()
+    }
+}
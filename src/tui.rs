@@ -1,9 +1,14 @@
 use crate::app::{App, AppResult};
 use crate::event::EventHandler;
 use crate::ui;
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crate::viewer::ReplayViewer;
+use crossterm::event::{
+    DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::Backend;
+use ratatui::layout::Rect;
 use ratatui::Terminal;
 use std::io;
 use std::panic;
@@ -31,7 +36,23 @@ impl<B: Backend> Tui<B> {
     /// It enables the raw mode and sets terminal properties.
     pub fn init(&mut self) -> AppResult<()> {
         terminal::enable_raw_mode()?;
-        crossterm::execute!(io::stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+        // focus changes let held keys be forgotten when releases can't arrive
+        crossterm::execute!(
+            io::stderr(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableFocusChange
+        )?;
+        // report key releases where the terminal can, for held keys
+        if terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            crossterm::execute!(
+                io::stderr(),
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                )
+            )?;
+        }
 
         // Define a custom panic hook to reset the terminal properties.
         // This way, you won't have your terminal messed up if an unexpected error happens.
@@ -55,13 +76,34 @@ impl<B: Backend> Tui<B> {
         Ok(())
     }
 
+    /// [`Draw`] the replay viewer.
+    ///
+    /// [`Draw`]: ratatui::Terminal::draw
+    pub fn draw_viewer(&mut self, viewer: &mut ReplayViewer) -> AppResult<()> {
+        self.terminal
+            .draw(|frame| ui::render_viewer(viewer, frame))?;
+        Ok(())
+    }
+
+    /// Size of the whole terminal.
+    pub fn size(&self) -> AppResult<Rect> {
+        Ok(self.terminal.size()?)
+    }
+
     /// Resets the terminal interface.
     ///
     /// This function is also used for the panic hook to revert
     /// the terminal properties if unexpected errors occur.
     fn reset() -> AppResult<()> {
         terminal::disable_raw_mode()?;
-        crossterm::execute!(io::stderr(), LeaveAlternateScreen, DisableMouseCapture)?;
+        // ignored by terminals that never had the flags pushed
+        crossterm::execute!(
+            io::stderr(),
+            PopKeyboardEnhancementFlags,
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableFocusChange
+        )?;
         Ok(())
     }
 
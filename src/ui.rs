@@ -1,9 +1,19 @@
 use ratatui::{
     prelude::*,
-    widgets::{canvas::Canvas, Block, BorderType, List},
+    widgets::{
+        canvas::{self, Canvas, Shape},
+        Block, BorderType, Clear, List, Paragraph,
+    },
 };
 
-use crate::{app::App, battle::DrawEnemy};
+use crate::{
+    app::{AimMode, App, GameResult, Interpolated, Player},
+    battle::DrawEnemy,
+    keymap::{Action, RebindMenu},
+    skill::Language,
+    viewer::ReplayViewer,
+    viewport::Viewport,
+};
 
 /// Renders the user interface widgets.
 pub fn render(app: &mut App, frame: &mut Frame) {
@@ -12,14 +22,204 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     // - https://docs.rs/ratatui/latest/ratatui/widgets/index.html
     // - https://github.com/ratatui-org/ratatui/tree/master/examples
 
+    render_app(app, frame, frame.size());
+}
+
+/// Renders the replay viewer, the game with a timeline below it.
+pub fn render_viewer(viewer: &mut ReplayViewer, frame: &mut Frame) {
+    let [main, timeline] = viewer_layout(frame.size());
+
+    render_app(&mut viewer.app, frame, main);
+    render_timeline(viewer, frame, timeline);
+}
+
+/// Splits the replay viewer into the game and the timeline below it
+fn viewer_layout(area: Rect) -> [Rect; 2] {
+    Layout::vertical([Constraint::Fill(1), Constraint::Length(4)]).areas(area)
+}
+
+/// Where the timeline bar is drawn when the viewer fills `area`, for mouse
+/// seeking to hit the same cells
+pub fn timeline_bar(area: Rect) -> Rect {
+    let [_, timeline] = viewer_layout(area);
+    Block::bordered().inner(timeline)
+}
+
+fn render_app(app: &mut App, frame: &mut Frame, area: Rect) {
     let [game_screen, info_panel] =
-        Layout::horizontal([Constraint::Fill(1), Constraint::Length(32)]).areas(frame.size());
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(32)]).areas(area);
     let [stage_screen, logs] =
         Layout::vertical([Constraint::Fill(1), Constraint::Length(10)]).areas(game_screen);
 
     render_game_screen(app, frame, stage_screen);
     render_player_info(app, frame, info_panel);
     render_game_logs(app, frame, logs);
+
+    if let Some(result) = app.result {
+        render_results(app, result, frame, stage_screen);
+    }
+    if let Some(menu) = &app.rebind {
+        render_rebind(app, menu, frame, stage_screen);
+    }
+}
+
+fn render_timeline(viewer: &ReplayViewer, frame: &mut Frame, area: Rect) {
+    let secs = |step: u64| step as f64 * App::FIXED_STEP.as_secs_f64();
+    let block = Block::bordered()
+        .title(format!(
+            "  Replay {} {:.2}x  {:.1}s / {:.1}s  ",
+            if viewer.paused { "⏸" } else { "▶" },
+            viewer.speed(),
+            secs(viewer.step()),
+            secs(viewer.length),
+        ))
+        .title_bottom(" space: pause  ←/→: seek  ,/.: frame  -/+: speed  [/]: stage  q: quit ")
+        .title_alignment(Alignment::Center)
+        .border_type(BorderType::Rounded);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [markers, bar] =
+        Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(inner);
+    let width = inner.width as usize;
+    if width == 0 {
+        return;
+    }
+    let column = |step: u64| {
+        let ratio = step as f64 / viewer.length.max(1) as f64;
+        ((ratio * (width - 1) as f64).round() as usize).min(width - 1)
+    };
+
+    // stage numbers above where each stage begins
+    let mut marks = vec![' '; width];
+    for (i, &start) in viewer.stage_starts.iter().enumerate() {
+        if let Some(c) = char::from_digit(i as u32, 36) {
+            marks[column(start)] = c;
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(marks.into_iter().collect::<String>()).fg(Color::DarkGray),
+        markers,
+    );
+
+    let played = column(viewer.step());
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled("━".repeat(played), Style::default().fg(Color::Yellow)),
+            Span::styled("●", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "─".repeat(width - played - 1),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])),
+        bar,
+    );
+}
+
+fn render_results(app: &App, result: GameResult, frame: &mut Frame, area: Rect) {
+    let [_, area, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(7),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    let [_, area, _] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Length(36),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+
+    let title = match result {
+        GameResult::Cleared => "  Cleared!  ",
+        GameResult::Defeated => "  Defeated  ",
+    };
+    let secs = app.steps as f64 * App::FIXED_STEP.as_secs_f64();
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::raw(format!("Stage: {}", app.stage_index)),
+            Line::raw(format!("Time: {:.1}s", secs)),
+            Line::raw(format!("HP: {}/{}", app.player.hp, app.player.max_hp)),
+            Line::raw(format!("Seed: {}", app.seed)),
+            Line::raw("Press q to quit"),
+        ])
+        .alignment(Alignment::Center)
+        .block(
+            Block::bordered()
+                .title(title)
+                .title_alignment(Alignment::Center)
+                .border_type(BorderType::Rounded),
+        ),
+        area,
+    );
+}
+
+fn render_rebind(app: &App, menu: &RebindMenu, frame: &mut Frame, area: Rect) {
+    let [_, area, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(Action::ALL.len() as u16 + 4),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    let [_, area, _] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Length(48),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+
+    let keymap = app.bindings.keymap();
+    let lines = Action::ALL.iter().enumerate().map(|(i, &action)| {
+        let keys = if i == menu.selected && menu.waiting {
+            "press a key...".to_string()
+        } else {
+            keymap
+                .keys(action)
+                .iter()
+                .filter_map(|k| k.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let line = Line::raw(format!("{:<14}{}", action.name(), keys));
+        if i == menu.selected {
+            line.style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        } else {
+            line
+        }
+    });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        List::new(
+            [
+                Line::raw(format!(
+                    "Profile: {}  (Tab to switch)",
+                    app.bindings.profile
+                )),
+                Line::raw(""),
+            ]
+            .into_iter()
+            .chain(lines),
+        )
+        .block(
+            Block::bordered()
+                .title("  Key Bindings  ")
+                .title_bottom(" ↑/↓: select  Enter: bind  Backspace: clear  Esc: close ")
+                .title_alignment(Alignment::Center)
+                .border_type(BorderType::Rounded),
+        ),
+        area,
+    );
+}
+
+fn dash_line(player: &Player) -> Line<'static> {
+    if player.dash_cooldown.is_zero() {
+        Line::styled("Dash: ready", Style::default().fg(Color::Cyan))
+    } else {
+        Line::raw(format!("Dash: {:.1}s", player.dash_cooldown.as_secs_f64()))
+    }
 }
 
 fn render_player_info(app: &mut App, frame: &mut Frame, area: Rect) {
@@ -32,8 +232,29 @@ fn render_player_info(app: &mut App, frame: &mut Frame, area: Rect) {
                     "Pos: ({:.2}, {:.2})",
                     app.player.pos_x, app.player.pos_y
                 )),
+                Line::raw(format!("Aim: {}", app.aim.name())),
+                dash_line(&app.player),
+                Line::raw(format!("Graze: {}", app.grazes())),
+                Line::raw(format!("Bombs: {}", app.player.bombs)),
             ]
             .into_iter()
+            .chain(
+                app.player
+                    .charge
+                    .map(|_| charge_meter(app.player.charge_ratio())),
+            )
+            .chain(loadout_lines(app))
+            .chain(app.player.statuses.iter().map(|e| {
+                Line::styled(
+                    format!(
+                        "{} x{} ({:.1}s)",
+                        e.kind.name(),
+                        e.stacks,
+                        e.remaining.as_secs_f64()
+                    ),
+                    Style::default().fg(e.kind.color()),
+                )
+            }))
             .chain(app.player.skills.iter().map(|s| Line::raw(&s.name))),
         )
         .block(
@@ -47,34 +268,235 @@ fn render_player_info(app: &mut App, frame: &mut Frame, area: Rect) {
 }
 
 fn render_game_screen(app: &mut App, frame: &mut Frame, area: Rect) {
-    let x_size = app.world_width;
-    let y_size = x_size * (area.height as f64 / area.width as f64) * App::CHAR_RATIO;
-
-    // HACK: modify data in rendering logic
-    app.canvas_rect = area;
+    let block = Block::bordered()
+        .title(format!(
+            "  嘴砲遊戲：Stage {}{}  ",
+            app.stage_index,
+            if app.paused { " (paused)" } else { "" }
+        ))
+        .title_alignment(Alignment::Center)
+        .border_type(BorderType::Rounded);
+    let viewport = app
+        .camera
+        .viewport(block.inner(area), &app.arena, app.alpha());
+    // kept for mouse input
+    app.viewport = viewport;
 
     frame.render_widget(
         Canvas::default()
             .marker(Marker::Braille)
-            .block(
-                Block::bordered()
-                    .title(format!("  嘴砲遊戲：Stage {}  ", app.stage_index))
-                    .title_alignment(Alignment::Center)
-                    .border_type(BorderType::Rounded),
-            )
+            .block(block)
             .paint(|ctx| {
+                ctx.draw(&app.arena);
+                for o in &app.obstacles {
+                    ctx.draw(o);
+                }
                 ctx.draw(&DrawEnemy(&app.enemy));
-                ctx.draw(&app.player);
+                let alpha = app.alpha();
+                ctx.draw(&Interpolated(&app.player, alpha));
                 for b in &app.bullets {
-                    ctx.draw(b);
+                    ctx.draw(&Interpolated(b, alpha));
+                }
+                ctx.draw(&aim_marker(app));
+                for arrow in offscreen_arrows(app, &viewport) {
+                    ctx.draw(&arrow);
+                }
+                if let Some((x, y)) = app.hover {
+                    ctx.draw(&Crosshair {
+                        x,
+                        y,
+                        size: 1.,
+                        color: if app.player.charge.is_some() {
+                            Color::Yellow
+                        } else {
+                            Color::White
+                        },
+                    });
                 }
             })
-            .x_bounds([-x_size, x_size])
-            .y_bounds([-y_size, y_size]),
+            .x_bounds(viewport.x_bounds)
+            .y_bounds(viewport.y_bounds),
         area,
     );
 }
 
+/// Every loadout, the one in use highlighted
+fn loadout_lines(app: &App) -> impl Iterator<Item = Line<'_>> {
+    let name = |slot: Option<Language>| slot.map_or("plain", |l| l.name());
+    app.player.loadouts.iter().enumerate().map(move |(i, l)| {
+        let active = i == app.player.loadout;
+        let line = format!(
+            "{} {}  L: {}  R: {}",
+            if active { "▶" } else { " " },
+            i + 1,
+            name(l.primary),
+            name(l.secondary)
+        );
+        if active {
+            Line::styled(line, Style::default().fg(Color::Black).bg(Color::Yellow))
+        } else {
+            Line::styled(line, Style::default().fg(Color::DarkGray))
+        }
+    })
+}
+
+fn charge_meter(ratio: f64) -> Line<'static> {
+    const WIDTH: usize = 16;
+    let filled = ((ratio * WIDTH as f64).round() as usize).min(WIDTH);
+    let color = if filled == WIDTH {
+        Color::LightRed
+    } else {
+        Color::Yellow
+    };
+    Line::from(vec![
+        Span::raw("Charge: "),
+        Span::styled("█".repeat(filled), Style::default().fg(color)),
+        Span::styled(
+            "░".repeat(WIDTH - filled),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ])
+}
+
+/// Where keyboard shots will go: a short mark in front of the player when
+/// aiming by facing, a crosshair otherwise
+fn aim_marker(app: &App) -> Crosshair {
+    const FACING_DISTANCE: f64 = 5.;
+
+    let player = &app.player;
+    match app.aim {
+        AimMode::Facing => Crosshair {
+            x: player.pos_x + player.face_x * FACING_DISTANCE,
+            y: player.pos_y + player.face_y * FACING_DISTANCE,
+            size: 0.5,
+            color: Color::DarkGray,
+        },
+        AimMode::Auto | AimMode::Cursor => {
+            let (x, y) = app.aim_target();
+            Crosshair {
+                x,
+                y,
+                size: 1.5,
+                color: Color::LightGreen,
+            }
+        }
+    }
+}
+
+/// Arrows at the edge of the view pointing at the enemy and at bullets
+/// coming in from out of view
+fn offscreen_arrows(app: &App, viewport: &Viewport) -> Vec<Arrow> {
+    /// How far out of view incoming bullets get an arrow
+    const BULLET_RANGE: f64 = 30.;
+    const MAX_BULLET_ARROWS: usize = 8;
+
+    let center_x = (viewport.x_bounds[0] + viewport.x_bounds[1]) / 2.;
+    let center_y = (viewport.y_bounds[0] + viewport.y_bounds[1]) / 2.;
+    // at the edge, a little inside so the whole arrow shows
+    let size = viewport.width() / 80.;
+    let half_w = viewport.width() / 2. - size * 2.;
+    let half_h = viewport.height() / 2. - size * 2.;
+    if half_w <= 0. || half_h <= 0. {
+        return vec![];
+    }
+    let arrow = |x: f64, y: f64, color: Color| {
+        let (dx, dy) = crate::norm(x - center_x, y - center_y);
+        let t = (half_w / dx.abs()).min(half_h / dy.abs());
+        Arrow {
+            x: center_x + dx * t,
+            y: center_y + dy * t,
+            dx,
+            dy,
+            size,
+            color,
+        }
+    };
+
+    let mut arrows = vec![];
+    let (x, y) = app.enemy.pos();
+    if !viewport.contains(x, y) {
+        arrows.push(arrow(x, y, Color::Red));
+    }
+
+    let distance_out = |x: f64, y: f64| {
+        let out_x = (viewport.x_bounds[0] - x)
+            .max(x - viewport.x_bounds[1])
+            .max(0.);
+        let out_y = (viewport.y_bounds[0] - y)
+            .max(y - viewport.y_bounds[1])
+            .max(0.);
+        out_x.hypot(out_y)
+    };
+    let mut incoming: Vec<_> = app
+        .bullets
+        .iter()
+        .filter(|b| !b.is_player && !viewport.contains(b.pos_x, b.pos_y))
+        .filter(|b| b.velocity_x * (center_x - b.pos_x) + b.velocity_y * (center_y - b.pos_y) > 0.)
+        .map(|b| (distance_out(b.pos_x, b.pos_y), b))
+        .filter(|(d, _)| *d < BULLET_RANGE)
+        .collect();
+    incoming.sort_by(|a, b| a.0.total_cmp(&b.0));
+    arrows.extend(
+        incoming
+            .iter()
+            .take(MAX_BULLET_ARROWS)
+            .map(|(_, b)| arrow(b.pos_x, b.pos_y, Color::LightRed)),
+    );
+    arrows
+}
+
+/// An arrowhead at (x, y) pointing along (dx, dy)
+struct Arrow {
+    x: f64,
+    y: f64,
+    dx: f64,
+    dy: f64,
+    size: f64,
+    color: Color,
+}
+
+impl Shape for Arrow {
+    fn draw(&self, painter: &mut canvas::Painter) {
+        let (back_x, back_y) = (self.x - self.dx * self.size, self.y - self.dy * self.size);
+        // the two barbs, either side of the shaft
+        let (side_x, side_y) = (-self.dy * self.size, self.dx * self.size);
+        for (x, y) in [
+            (back_x + side_x, back_y + side_y),
+            (back_x - side_x, back_y - side_y),
+        ] {
+            canvas::Line::new(x, y, self.x, self.y, self.color).draw(painter);
+        }
+    }
+}
+
+struct Crosshair {
+    x: f64,
+    y: f64,
+    size: f64,
+    color: Color,
+}
+
+impl Shape for Crosshair {
+    fn draw(&self, painter: &mut canvas::Painter) {
+        let horizontal = canvas::Line::new(
+            self.x - self.size,
+            self.y,
+            self.x + self.size,
+            self.y,
+            self.color,
+        );
+        let vertical = canvas::Line::new(
+            self.x,
+            self.y - self.size,
+            self.x,
+            self.y + self.size,
+            self.color,
+        );
+        horizontal.draw(painter);
+        vertical.draw(painter);
+    }
+}
+
 fn render_game_logs(app: &mut App, frame: &mut Frame, area: Rect) {
     frame.render_widget(
         List::new(
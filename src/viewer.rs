@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+
+use crate::app::{App, AppResult};
+use crate::replay::Replay;
+use crate::ui;
+
+/// Watch a replay with pause, seek and speed control.
+///
+/// The whole replay is simulated once up front, keeping a snapshot of the
+/// game every [`ReplayViewer::SNAPSHOT_INTERVAL`] steps, so seeking only has
+/// to re-simulate from the closest snapshot.
+#[derive(Debug)]
+pub struct ReplayViewer {
+    /// Is the viewer running?
+    pub running: bool,
+    /// The game being watched
+    pub app: App,
+    pub paused: bool,
+    /// Index into [`ReplayViewer::SPEEDS`]
+    speed: usize,
+    /// Steps in the replay
+    pub length: u64,
+    /// Steps where each stage begins
+    pub stage_starts: Vec<u64>,
+    /// Game states every `SNAPSHOT_INTERVAL` steps, without playback
+    snapshots: Vec<App>,
+    /// Elapsed time not simulated yet
+    accumulator: Duration,
+    /// Terminal size, to find the timeline when clicked
+    area: Rect,
+}
+
+impl ReplayViewer {
+    pub const SPEEDS: [f64; 5] = [0.25, 0.5, 1., 2., 4.];
+    const SNAPSHOT_INTERVAL: u64 = 150;
+    /// Steps skipped by a single seek
+    const SEEK_STEPS: u64 = 150;
+
+    pub fn new(replay: Replay) -> Self {
+        let mut app = App::from_replay(replay);
+        let mut snapshots = vec![];
+        let mut stage_starts = vec![0];
+
+        let length = app.playback.as_ref().map_or(0, |r| r.length);
+        while app.steps < length && app.result.is_none() {
+            if app.steps == snapshots.len() as u64 * Self::SNAPSHOT_INTERVAL {
+                snapshots.push(Self::snapshot(&mut app));
+            }
+            let stage = app.stage_index;
+            app.step();
+            if app.stage_index != stage {
+                stage_starts.push(app.steps);
+            }
+        }
+        if snapshots.is_empty() {
+            snapshots.push(Self::snapshot(&mut app));
+        }
+
+        let mut viewer = Self {
+            running: true,
+            length: app.steps,
+            app,
+            paused: false,
+            speed: 2,
+            stage_starts,
+            snapshots,
+            accumulator: Duration::ZERO,
+            area: Rect::default(),
+        };
+        viewer.seek(0);
+        viewer
+    }
+
+    /// Clone the game state, leaving the replay out of it
+    fn snapshot(app: &mut App) -> App {
+        let playback = app.playback.take();
+        let snapshot = app.clone();
+        app.playback = playback;
+        snapshot
+    }
+
+    pub fn step(&self) -> u64 {
+        self.app.steps
+    }
+
+    pub fn speed(&self) -> f64 {
+        Self::SPEEDS[self.speed]
+    }
+
+    /// Handles the tick event of the terminal.
+    pub fn update(&mut self, elapsed: Duration) {
+        if self.paused {
+            return;
+        }
+
+        self.accumulator += elapsed.mul_f64(self.speed());
+        while self.accumulator >= App::FIXED_STEP {
+            self.accumulator -= App::FIXED_STEP;
+            self.step_forward();
+        }
+    }
+
+    /// Advance one step
+    pub fn step_forward(&mut self) {
+        if self.step() < self.length {
+            self.app.step();
+        }
+    }
+
+    /// Go back one step
+    pub fn step_back(&mut self) {
+        self.seek(self.step().saturating_sub(1));
+    }
+
+    /// Restore the closest snapshot before `step` and simulate up to it
+    pub fn seek(&mut self, step: u64) {
+        let step = step.min(self.length);
+        let i = self.snapshots.partition_point(|s| s.steps <= step) - 1;
+
+        let mut replay = self.app.playback.take();
+        let mut app = self.snapshots[i].clone();
+        if let Some(replay) = &mut replay {
+            replay.seek(app.steps);
+        }
+        app.playback = replay;
+        app.viewport = self.app.viewport;
+
+        while app.steps < step && app.result.is_none() {
+            app.step();
+        }
+        self.app = app;
+        self.accumulator = Duration::ZERO;
+    }
+
+    pub fn seek_forward(&mut self) {
+        self.seek(self.step() + Self::SEEK_STEPS);
+    }
+
+    pub fn seek_backward(&mut self) {
+        self.seek(self.step().saturating_sub(Self::SEEK_STEPS));
+    }
+
+    /// Jump to the start of the next stage
+    pub fn next_stage(&mut self) {
+        let step = self.step();
+        if let Some(&start) = self.stage_starts.iter().find(|&&s| s > step) {
+            self.seek(start);
+        }
+    }
+
+    /// Jump to the start of the current stage, or the previous one if we're
+    /// right at the start already
+    pub fn prev_stage(&mut self) {
+        const GRACE: u64 = 30;
+        let step = self.step();
+        if let Some(&start) = self.stage_starts.iter().rev().find(|&&s| s + GRACE < step) {
+            self.seek(start);
+        } else {
+            self.seek(0);
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(Self::SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    /// Handles the terminal being resized, and its size at startup.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.area = Rect::new(0, 0, width, height);
+    }
+
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
+
+    /// Click or drag on the timeline to seek
+    pub fn on_mouse_event(&mut self, evt: MouseEvent) -> AppResult<()> {
+        match evt.kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+                let rect = ui::timeline_bar(self.area);
+                if rect.width == 0
+                    || !(rect.x..rect.x + rect.width).contains(&evt.column)
+                    || !(rect.y..rect.y + rect.height).contains(&evt.row)
+                {
+                    return Ok(());
+                }
+
+                let ratio = (evt.column - rect.x) as f64 / (rect.width - 1).max(1) as f64;
+                self.seek((ratio * self.length as f64).round() as u64);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use ratatui::layout::Rect;
+
+use crate::app::App;
+
+/// The part of the world shown on the game canvas, and which terminal cells
+/// it's drawn in.
+///
+/// Mirrors how ratatui's braille canvas places points, so a world position
+/// maps to the cell it's drawn in and a cell maps back to a world position
+/// drawn in that same cell.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Viewport {
+    /// Cells the canvas draws in, inside the block borders
+    pub area: Rect,
+    /// `[left, right]` world coordinates shown
+    pub x_bounds: [f64; 2],
+    /// `[bottom, top]` world coordinates shown
+    pub y_bounds: [f64; 2],
+}
+
+impl Viewport {
+    /// Braille dots per cell
+    const DOTS_X: f64 = 2.;
+    const DOTS_Y: f64 = 4.;
+
+    /// Show `half_width` world units either side of `center` horizontally,
+    /// and as much vertically as keeps the world's aspect ratio in `area`
+    pub fn new(area: Rect, center: (f64, f64), half_width: f64) -> Self {
+        let half_height = if area.width == 0 {
+            0.
+        } else {
+            half_width * (area.height as f64 / area.width as f64) * App::CHAR_RATIO
+        };
+        Self {
+            area,
+            x_bounds: [center.0 - half_width, center.0 + half_width],
+            y_bounds: [center.1 - half_height, center.1 + half_height],
+        }
+    }
+
+    /// Braille dots across the canvas, minus one as ratatui divides by that
+    fn dots(&self) -> (f64, f64) {
+        (
+            self.area.width as f64 * Self::DOTS_X - 1.,
+            self.area.height as f64 * Self::DOTS_Y - 1.,
+        )
+    }
+
+    pub fn width(&self) -> f64 {
+        self.x_bounds[1] - self.x_bounds[0]
+    }
+
+    pub fn height(&self) -> f64 {
+        self.y_bounds[1] - self.y_bounds[0]
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        (self.x_bounds[0]..=self.x_bounds[1]).contains(&x)
+            && (self.y_bounds[0]..=self.y_bounds[1]).contains(&y)
+    }
+
+    /// The cell a world position is drawn in, `None` if it's out of view
+    pub fn to_screen(&self, x: f64, y: f64) -> Option<(u16, u16)> {
+        if !self.contains(x, y) || self.width() <= 0. || self.height() <= 0. {
+            return None;
+        }
+        let (dots_x, dots_y) = self.dots();
+        let dot_x = ((x - self.x_bounds[0]) * dots_x / self.width()) as u16;
+        let dot_y = ((self.y_bounds[1] - y) * dots_y / self.height()) as u16;
+        Some((
+            self.area.x + dot_x / Self::DOTS_X as u16,
+            self.area.y + dot_y / Self::DOTS_Y as u16,
+        ))
+    }
+
+    /// World position in the middle of a cell, `None` outside the canvas
+    pub fn to_world(&self, column: u16, row: u16) -> Option<(f64, f64)> {
+        let area = self.area;
+        if !(area.x..area.x + area.width).contains(&column)
+            || !(area.y..area.y + area.height).contains(&row)
+        {
+            return None;
+        }
+        let (dots_x, dots_y) = self.dots();
+        let dot_x = (column - area.x) as f64 * Self::DOTS_X + Self::DOTS_X / 2. - 0.5;
+        let dot_y = (row - area.y) as f64 * Self::DOTS_Y + Self::DOTS_Y / 2. - 0.5;
+        Some((
+            self.x_bounds[0] + dot_x * self.width() / dots_x,
+            self.y_bounds[1] - dot_y * self.height() / dots_y,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZES: [u16; 10] = [0, 1, 2, 3, 7, 16, 33, 80, 121, 255];
+    const OFFSETS: [u16; 3] = [0, 1, 13];
+
+    fn viewports() -> impl Iterator<Item = Viewport> {
+        SIZES.into_iter().flat_map(|width| {
+            SIZES.into_iter().flat_map(move |height| {
+                OFFSETS.into_iter().flat_map(move |offset| {
+                    let area = Rect::new(offset, offset * 2, width, height);
+                    [((0., 0.), 100.), ((-37.5, 12.25), 31.7), ((1e3, -2e3), 0.5)]
+                        .into_iter()
+                        .map(move |(center, half_width)| Viewport::new(area, center, half_width))
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn round_trip() {
+        for view in viewports() {
+            let area = view.area;
+            for row in area.y..area.y + area.height {
+                for column in area.x..area.x + area.width {
+                    let (x, y) = view.to_world(column, row).unwrap();
+                    assert_eq!(
+                        view.to_screen(x, y),
+                        Some((column, row)),
+                        "{view:?} cell ({column}, {row})"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Cells ratatui's braille canvas actually draws a point in
+    #[test]
+    fn matches_canvas() {
+        use ratatui::buffer::Buffer;
+        use ratatui::style::Color;
+        use ratatui::widgets::canvas::{Canvas, Points};
+        use ratatui::widgets::Widget;
+
+        for view in viewports().filter(|v| v.area.width <= 16 && v.area.height <= 16) {
+            let area = view.area;
+            for row in area.y..area.y + area.height {
+                for column in area.x..area.x + area.width {
+                    let (x, y) = view.to_world(column, row).unwrap();
+                    let mut buf = Buffer::empty(area);
+                    Canvas::default()
+                        .x_bounds(view.x_bounds)
+                        .y_bounds(view.y_bounds)
+                        .paint(|ctx| {
+                            ctx.draw(&Points {
+                                coords: &[(x, y)],
+                                color: Color::White,
+                            })
+                        })
+                        .render(area, &mut buf);
+                    assert_ne!(
+                        buf.get(column, row).symbol(),
+                        " ",
+                        "{view:?} cell ({column}, {row})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn outside_the_canvas() {
+        for view in viewports() {
+            let area = view.area;
+            assert_eq!(view.to_world(area.x + area.width, area.y), None);
+            assert_eq!(view.to_world(area.x, area.y + area.height), None);
+            if area.x > 0 {
+                assert_eq!(view.to_world(area.x - 1, area.y), None);
+            }
+            let right = view.x_bounds[1] + 1.;
+            assert_eq!(view.to_screen(right, view.y_bounds[0]), None);
+        }
+    }
+}